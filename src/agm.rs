@@ -0,0 +1,121 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{hyper_graph::HyperGraph, hypertree};
+
+/*
+    AGM bound (Atserias, Grohe, Marx) on the number of join tuples of a query: for every fractional edge cover of its hypergraph,
+    the join has at most the product of the cardinalities of the relations of the atoms raised to their weights.
+    The tightest bound comes from the cover minimizing the sum of the weights multiplied by the logarithms of the cardinalities,
+    and some database with those cardinalities reaches it up to a constant factor.
+*/
+#[derive(Clone, Debug)]
+pub struct AgmBound {
+    atoms: Vec<String>,
+    cardinalities: Vec<usize>,
+    weights: Vec<f64>,
+    bound: f64,
+    cover_number: f64,
+}
+
+impl AgmBound {
+    // Bound for a hypergraph whose hyper-edges are the atoms with the given names, whose relations have the given cardinalities
+    pub fn new(h: &HyperGraph, atoms: &[String], cardinalities: &[usize]) -> AgmBound {
+        let vertices = h.vertices();
+        let edges = h.edges();
+        let costs: Vec<f64> = cardinalities
+            .iter()
+            .map(|x| (*x.max(&1) as f64).ln())
+            .collect();
+        let (log_bound, weights) =
+            hypertree::weighted_fractional_edge_cover(&vertices, &edges, &costs);
+        // The weights read from the dual solution can come out as negative zeros
+        let mut weights: Vec<f64> = (weights.iter())
+            .map(|x| if *x > 0.0 { *x } else { 0.0 })
+            .collect();
+        let mut bound = log_bound.exp();
+        // An empty relation empties the join: it can be added to the cover with weight 1, bringing the bound down to 0
+        for (i, cardinality) in cardinalities.iter().enumerate() {
+            if *cardinality == 0 {
+                weights[i] = weights[i].max(1.0);
+                bound = 0.0;
+            }
+        }
+        // Like the weights, the cover number of a hypergraph without vertices can come out as a negative zero
+        let cover_number = match hypertree::fractional_edge_cover(&vertices, &edges).0 {
+            x if x > 0.0 => x,
+            _ => 0.0,
+        };
+        AgmBound {
+            atoms: atoms.to_vec(),
+            cardinalities: cardinalities.to_vec(),
+            weights,
+            bound,
+            cover_number,
+        }
+    }
+
+    pub fn bound(&self) -> f64 {
+        self.bound
+    }
+
+    // Fractional edge cover number of the hypergraph, the exponent of the bound when all the relations have the same cardinality
+    pub fn cover_number(&self) -> f64 {
+        self.cover_number
+    }
+
+    // Every atom with the cardinality of its relation and its weight in the cover achieving the bound
+    pub fn weights(&self) -> Vec<(String, usize, f64)> {
+        let mut result = Vec::new();
+        for (i, atom) in self.atoms.iter().enumerate() {
+            result.push((atom.clone(), self.cardinalities[i], self.weights[i]));
+        }
+        result
+    }
+}
+
+impl Display for AgmBound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "AGM bound: {:.1} join tuples (fractional edge cover number {:.3})",
+            self.bound, self.cover_number
+        )?;
+        for (atom, cardinality, weight) in self.weights() {
+            writeln!(
+                f,
+                "  {}: {} records, weight {:.3}",
+                atom, cardinality, weight
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conjunctive_query::ConjunctiveQuery;
+
+    fn agm_bound(query: &str, cardinalities: &[usize]) -> AgmBound {
+        let cq = ConjunctiveQuery::new(query);
+        let atoms: Vec<String> = cq.atoms().iter().map(|x| x.get_name()).collect();
+        AgmBound::new(&HyperGraph::new(&cq), &atoms, cardinalities)
+    }
+
+    #[test]
+    fn triangle() {
+        let agm = agm_bound("q(x, y, z) :- R(x, y), S(y, z), T(z, x)", &[100, 100, 100]);
+        assert!((agm.cover_number() - 1.5).abs() < 1e-9);
+        assert!((agm.bound() - 100f64.powf(1.5)).abs() < 1e-6);
+        for (_, _, weight) in agm.weights() {
+            assert!((weight - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn query_without_variables() {
+        let agm = agm_bound("q() :- R(1, 2)", &[10]);
+        assert_eq!(format!("{:.3}", agm.cover_number()), "0.000");
+        assert!((agm.bound() - 1.0).abs() < 1e-9);
+    }
+}
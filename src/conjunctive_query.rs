@@ -1,43 +1,352 @@
 use rand::Rng;
-use regex::Regex;
-use std::{collections::HashSet, fs::File, io::Write};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::Write,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticKind, Location},
+    homomorphism::{self, Homomorphism, UnsupportedQueryError},
+    hyper_graph::{AcyclicityProfile, HyperGraph},
+    parse_error::ParseError,
+    parser::{self, AtomNode, RuleNode, TermNode},
+    schema::Schema,
+    sql::{self, ColumnNaming},
+};
 
 const N_MAX_RECORDS: usize = 10000000;
 const MAX_RECORD_VALUE: usize = 300000;
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constant {
+    Number(String),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    Variable(String),
+    Constant(Constant),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+// Built-in predicate comparing two terms, e.g. x < z or y != 3
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    left: Term,
+    operator: ComparisonOperator,
+    right: Term,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+// Aggregate in the head of a query, grouping by the head variables; count(*) is the only one without a variable
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    function: AggregateFunction,
+    variable: Option<String>,
+}
+
+// How hard a query is to evaluate, according to the shape of its hypergraph
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryClass {
+    // Alpha-acyclic query without head variables, decided in linear time
+    Boolean,
+    // Alpha-acyclic query that stays so with a hyper-edge for the head, answers are enumerated with constant delay
+    FreeConnex,
+    // Alpha-acyclic query that is not free-connex, answered in time linear in the input and the output
+    AcyclicNonFreeConnex,
+    // Query that is not alpha-acyclic, Yannakakis algorithm doesn't apply
+    Cyclic,
+}
+
+/*
+    The name of an atom identifies it inside its query, while the relation name is the one of the table the atom is evaluated on.
+    They are the same, unless the relation is used by more than one atom of the query (self-join): in that case the k-th atom
+    over relation R is named R#k, that can't clash with any other relation name.
+*/
 #[derive(Clone)]
 pub struct Atom {
     name: String,
-    variables: Vec<String>,
+    relation_name: String,
+    terms: Vec<Term>,
 }
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct ConjunctiveQuery {
     atoms_list: Vec<Atom>,
+    negated_atoms: Vec<Atom>,
+    comparisons: Vec<Comparison>,
     query_name: String,
     head_variables: Vec<String>,
+    // Aggregates in the head, together with their position among all the head terms
+    head_aggregates: Vec<(usize, Aggregate)>,
     is_boolean: bool,
 }
 
+impl Constant {
+    // Textual value of the constant, without quotes, to be parsed into the type of the database values
+    pub fn value(&self) -> &str {
+        match self {
+            Constant::Number(value) => value,
+            Constant::Text(value) => value,
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Number(value) => write!(f, "{}", value),
+            Constant::Text(value) => write!(
+                f,
+                "\"{}\"",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+        }
+    }
+}
+
+impl Term {
+    /*
+        Every occurrence of the anonymous variable _ gets its own internal name _#k, that can't clash with any other variable name,
+        so that it never joins with other terms.
+    */
+    pub fn is_anonymous(&self) -> bool {
+        matches!(self, Term::Variable(name) if name.starts_with("_#"))
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Variable(_) if self.is_anonymous() => write!(f, "_"),
+            Term::Variable(name) => write!(f, "{}", name),
+            Term::Constant(constant) => write!(f, "{}", constant),
+        }
+    }
+}
+
+impl ComparisonOperator {
+    // Recognize the operator at the beginning of the input, returning it together with its length
+    pub fn lex(input: &str) -> Option<(ComparisonOperator, usize)> {
+        let operators = [
+            ("!=", ComparisonOperator::NotEqual),
+            ("<>", ComparisonOperator::NotEqual),
+            ("<=", ComparisonOperator::LessEqual),
+            (">=", ComparisonOperator::GreaterEqual),
+            ("=", ComparisonOperator::Equal),
+            ("<", ComparisonOperator::Less),
+            (">", ComparisonOperator::Greater),
+        ];
+        for (symbol, operator) in operators {
+            if input.starts_with(symbol) {
+                return Some((operator, symbol.len()));
+            }
+        }
+        None
+    }
+
+    pub fn compare<T: Ord>(&self, left: &T, right: &T) -> bool {
+        match self {
+            ComparisonOperator::Equal => left == right,
+            ComparisonOperator::NotEqual => left != right,
+            ComparisonOperator::Less => left < right,
+            ComparisonOperator::LessEqual => left <= right,
+            ComparisonOperator::Greater => left > right,
+            ComparisonOperator::GreaterEqual => left >= right,
+        }
+    }
+}
+
+impl Display for ComparisonOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            ComparisonOperator::Equal => "=",
+            ComparisonOperator::NotEqual => "!=",
+            ComparisonOperator::Less => "<",
+            ComparisonOperator::LessEqual => "<=",
+            ComparisonOperator::Greater => ">",
+            ComparisonOperator::GreaterEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Display for QueryClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            QueryClass::Boolean => "boolean (acyclic, decided in linear time)",
+            QueryClass::FreeConnex => {
+                "free-connex (answers enumerated with constant delay after linear preprocessing)"
+            }
+            QueryClass::AcyclicNonFreeConnex => {
+                "acyclic, not free-connex (answered in time linear in the input and the output)"
+            }
+            QueryClass::Cyclic => "cyclic (Yannakakis algorithm doesn't apply)",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<AggregateFunction> {
+        match name.to_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Aggregate {
+    pub fn new(function: AggregateFunction, variable: Option<String>) -> Aggregate {
+        Aggregate { function, variable }
+    }
+
+    pub fn get_function(&self) -> AggregateFunction {
+        self.function
+    }
+
+    pub fn get_variable(&self) -> Option<String> {
+        self.variable.clone()
+    }
+}
+
+impl Display for Aggregate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.variable {
+            Some(variable) => write!(f, "{}({})", self.function, variable),
+            None => write!(f, "{}(*)", self.function),
+        }
+    }
+}
+
+impl Comparison {
+    pub fn new(left: Term, operator: ComparisonOperator, right: Term) -> Comparison {
+        Comparison {
+            left,
+            operator,
+            right,
+        }
+    }
+
+    pub fn get_left(&self) -> Term {
+        self.left.clone()
+    }
+
+    pub fn get_operator(&self) -> ComparisonOperator {
+        self.operator
+    }
+
+    pub fn get_right(&self) -> Term {
+        self.right.clone()
+    }
+
+    pub fn get_variables(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        for term in [&self.left, &self.right] {
+            if let Term::Variable(variable) = term {
+                if !result.contains(variable) {
+                    result.push(variable.clone());
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.left, self.operator, self.right)
+    }
+}
+
 impl Atom {
     pub fn new() -> Atom {
         Atom {
             name: String::from(""),
-            variables: Vec::new(),
+            relation_name: String::from(""),
+            terms: Vec::new(),
         }
     }
 
+    // Distinct variables of the atom, in order of first occurrence, anonymous ones excluded since they don't join
     pub fn get_variables(&self) -> Vec<String> {
-        self.variables.clone()
+        let mut result = Vec::new();
+        for term in &self.terms {
+            if let Term::Variable(variable) = term {
+                if !term.is_anonymous() && !result.contains(variable) {
+                    result.push(variable.clone());
+                }
+            }
+        }
+        result
+    }
+
+    pub fn get_terms(&self) -> Vec<Term> {
+        self.terms.clone()
+    }
+
+    pub fn has_constants(&self) -> bool {
+        self.terms.iter().any(|x| matches!(x, Term::Constant(_)))
+    }
+
+    pub fn has_anonymous_variables(&self) -> bool {
+        self.terms.iter().any(|x| x.is_anonymous())
+    }
+
+    pub fn has_repeated_variables(&self) -> bool {
+        let n_variables = self
+            .terms
+            .iter()
+            .filter(|x| matches!(x, Term::Variable(_)) && !x.is_anonymous())
+            .count();
+        n_variables != self.get_variables().len()
     }
 
     pub fn get_name(&self) -> String {
         return self.name.clone();
     }
 
+    pub fn get_relation_name(&self) -> String {
+        self.relation_name.clone()
+    }
+
     pub fn intersect(&self, other: &Atom) -> Vec<String> {
         let mut result = Vec::new();
-        for variable in &self.variables {
-            for v in &other.variables {
+        for variable in &self.get_variables() {
+            for v in &other.get_variables() {
                 if variable == v {
                     result.push(variable.clone());
                 }
@@ -46,34 +355,15 @@ impl Atom {
         result
     }
 
-    pub fn to_string(&self) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        result += format!("{}( ", self.name).as_str();
-        for variable in &self.variables {
-            result += format!("{}", variable).as_str();
-            if i == (self.variables.len() - 1) {
-                result += " ";
-            } else {
-                result += ", ";
-            }
-            i += 1;
-        }
-        result += ")";
-        result
-    }
-
     pub fn print(&self) {
-        let mut i = 0;
         print!("{}( ", self.name);
-        for variable in &self.variables {
-            print!("{}", variable);
-            if i == (self.variables.len() - 1) {
+        for (i, term) in self.terms.iter().enumerate() {
+            print!("{}", term);
+            if i == (self.terms.len() - 1) {
                 print!(" ");
             } else {
                 print!(", ");
             }
-            i += 1;
         }
         print!(")");
     }
@@ -81,76 +371,420 @@ impl Atom {
 
 impl PartialEq for Atom {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.variables == other.variables
+        self.name == other.name
+            && self.relation_name == other.relation_name
+            && self.terms == other.terms
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}(", self.relation_name)?;
+        for (i, term) in self.terms.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", term)?;
+        }
+        write!(f, ")")
     }
 }
 
 impl ConjunctiveQuery {
     pub fn new(text_description: &str) -> ConjunctiveQuery {
+        match ConjunctiveQuery::parse(text_description) {
+            Ok(result) => result,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn parse(text_description: &str) -> Result<ConjunctiveQuery, ParseError> {
+        let rule = parser::parse_rule(text_description)?;
+        ConjunctiveQuery::from_valid_rule(text_description, &rule, None)
+    }
+
+    // Translate a select-project-join SQL query over the relations of the schema
+    pub fn from_sql(
+        text_description: &str,
+        schema: &Schema,
+    ) -> Result<ConjunctiveQuery, ParseError> {
+        sql::parse_sql(text_description, schema)
+    }
+
+    /*
+        Check whether every answer of this query is an answer of the other one on every database (Chandra-Merlin),
+        returning the containment mapping from the other query into this one, if any.
+        The check is polynomial when the other query is alpha-acyclic. Queries that are not pure (see is_pure) are an error.
+    */
+    pub fn is_contained_in(
+        &self,
+        other: &ConjunctiveQuery,
+    ) -> Result<Option<Homomorphism>, UnsupportedQueryError> {
+        self.check_pure()?;
+        other.check_pure()?;
+        Ok(homomorphism::find_homomorphism(other, self))
+    }
+
+    pub fn is_equivalent_to(
+        &self,
+        other: &ConjunctiveQuery,
+    ) -> Result<bool, UnsupportedQueryError> {
+        Ok(self.is_contained_in(other)?.is_some() && other.is_contained_in(self)?.is_some())
+    }
+
+    // Whether the query has neither negated atoms, nor comparisons, nor aggregates, as required by the containment checks
+    pub fn is_pure(&self) -> bool {
+        self.negated_atoms.is_empty() && self.comparisons.is_empty() && !self.is_aggregate()
+    }
+
+    fn check_pure(&self) -> Result<(), UnsupportedQueryError> {
+        if !self.is_pure() {
+            return Err(UnsupportedQueryError::new(&self.to_string()));
+        }
+        Ok(())
+    }
+
+    /*
+        Compute the core of the query, i.e. an equivalent query with the minimum number of atoms, returning it together with the removed atoms.
+        An atom is removed whenever the query maps into the other atoms fixing the head, one pass is enough since an atom that can't be removed
+        from the query can't be removed from any of its equivalent subqueries either.
+        Queries that are not pure (see is_pure) are an error.
+    */
+    pub fn minimize(&self) -> Result<(ConjunctiveQuery, Vec<Atom>), UnsupportedQueryError> {
+        self.check_pure()?;
+        let mut result = self.clone();
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while i < result.atoms_list.len() {
+            let mut candidate = result.clone();
+            let atom = candidate.atoms_list.remove(i);
+            if homomorphism::find_homomorphism(&result, &candidate).is_some() {
+                result = candidate;
+                removed.push(atom);
+            } else {
+                i += 1;
+            }
+        }
+        ConjunctiveQuery::name_atoms(&mut result.atoms_list);
+        Ok((result, removed))
+    }
+
+    /*
+        Classify the query by its hypergraph, where the head variables of an aggregate query are the group by ones.
+        A boolean query is reported as such only when it is acyclic.
+    */
+    pub fn classify(&self) -> QueryClass {
+        let h = HyperGraph::new(self);
+        if !h.is_acyclic() {
+            QueryClass::Cyclic
+        } else if self.is_boolean {
+            QueryClass::Boolean
+        } else if h.is_free_connex(&self.head_variables) {
+            QueryClass::FreeConnex
+        } else {
+            QueryClass::AcyclicNonFreeConnex
+        }
+    }
+
+    // Acyclicity of the hypergraph of the query at the Berge, gamma, beta and alpha levels
+    pub fn acyclicity_profile(&self) -> AcyclicityProfile {
+        HyperGraph::new(self).acyclicity_profile()
+    }
+
+    // Render the query as an equivalent SQL statement over its relations
+    pub fn to_sql(&self, naming: &ColumnNaming) -> Result<String, Diagnostic> {
+        sql::to_sql(self, naming)
+    }
+
+    // Parse a query checking also that its atoms match the relations of the schema
+    pub fn parse_with_schema(
+        text_description: &str,
+        schema: &Schema,
+    ) -> Result<ConjunctiveQuery, ParseError> {
+        let rule = parser::parse_rule(text_description)?;
+        ConjunctiveQuery::from_valid_rule(text_description, &rule, Some(schema))
+    }
+
+    // Build the query of a rule, turning the first validation error into a parse error pointing at the wrong part of the rule
+    pub fn from_valid_rule(
+        text_description: &str,
+        rule: &RuleNode,
+        schema: Option<&Schema>,
+    ) -> Result<ConjunctiveQuery, ParseError> {
+        let result = ConjunctiveQuery::from_rule(rule);
+        match result.validate(schema).into_iter().find(|x| x.is_error()) {
+            Some(diagnostic) => Err(ParseError::unexpected(
+                text_description,
+                ConjunctiveQuery::diagnostic_offset(rule, &diagnostic),
+                &diagnostic.expected(),
+                &diagnostic.found(),
+            )),
+            None => Ok(result),
+        }
+    }
+
+    fn diagnostic_offset(rule: &RuleNode, diagnostic: &Diagnostic) -> usize {
+        // Point at the term holding the variable of the diagnostic if any, at the whole atom or comparison otherwise
+        let variable_offset = |terms: Vec<&TermNode>, default: usize| {
+            terms
+                .into_iter()
+                .find(|x| matches!(&x.term, Term::Variable(v) if Some(v) == diagnostic.variable().as_ref()))
+                .map_or(default, |x| x.offset)
+        };
+        match diagnostic.location() {
+            Location::Query => rule.head.offset,
+            Location::Head(position) => {
+                match rule.head_aggregates.iter().find(|x| x.position == position) {
+                    Some(aggregate) => aggregate.offset,
+                    None => {
+                        let aggregates_before = rule
+                            .head_aggregates
+                            .iter()
+                            .filter(|x| x.position < position)
+                            .count();
+                        rule.head.terms[position - aggregates_before].offset
+                    }
+                }
+            }
+            Location::Atom(i) => rule.body[i].offset,
+            Location::NegatedAtom(i) => variable_offset(
+                rule.negated_body[i].terms.iter().collect(),
+                rule.negated_body[i].offset,
+            ),
+            Location::Comparison(i) => {
+                let comparison = &rule.comparisons[i];
+                variable_offset(
+                    vec![&comparison.left, &comparison.right],
+                    comparison.left.offset,
+                )
+            }
+        }
+    }
+
+    /*
+        Check that the query can be evaluated: every variable of the head, of an aggregate, of a negated atom or of a comparison
+        must occur in a positive atom, otherwise its values would depend on the whole domain, and the body must have a positive atom.
+        When a schema is given, every atom must refer to one of its relations with the right number of terms.
+        Atoms repeated in the body are reported as warnings, since they don't change the answers.
+    */
+    pub fn validate(&self, schema: Option<&Schema>) -> Vec<Diagnostic> {
+        let mut result = Vec::new();
+        if self.atoms_list.is_empty() {
+            result.push(Diagnostic::new(DiagnosticKind::EmptyBody, Location::Query));
+        }
+        let positive_variables: HashSet<String> = self
+            .atoms_list
+            .iter()
+            .flat_map(|x| x.get_variables())
+            .collect();
+        let aggregate_positions: Vec<usize> = self.head_aggregates.iter().map(|x| x.0).collect();
+        let variable_positions = (0..).filter(|x| !aggregate_positions.contains(x));
+        for (variable, position) in self.head_variables.iter().zip(variable_positions) {
+            if !positive_variables.contains(variable) {
+                result.push(Diagnostic::new(
+                    DiagnosticKind::UnsafeHeadVariable(variable.clone()),
+                    Location::Head(position),
+                ));
+            }
+        }
+        for (position, aggregate) in &self.head_aggregates {
+            if let Some(variable) = aggregate.get_variable() {
+                if !positive_variables.contains(&variable) {
+                    result.push(Diagnostic::new(
+                        DiagnosticKind::UnsafeAggregateVariable(variable),
+                        Location::Head(*position),
+                    ));
+                }
+            }
+        }
+        for (i, atom) in self.negated_atoms.iter().enumerate() {
+            for variable in atom.get_variables() {
+                if !positive_variables.contains(&variable) {
+                    result.push(Diagnostic::new(
+                        DiagnosticKind::UnsafeNegatedVariable(variable),
+                        Location::NegatedAtom(i),
+                    ));
+                }
+            }
+        }
+        for (i, comparison) in self.comparisons.iter().enumerate() {
+            for variable in comparison.get_variables() {
+                if !positive_variables.contains(&variable) {
+                    result.push(Diagnostic::new(
+                        DiagnosticKind::UnsafeComparisonVariable(variable),
+                        Location::Comparison(i),
+                    ));
+                }
+            }
+        }
+        if let Some(schema) = schema {
+            let atoms = (self.atoms_list.iter().enumerate())
+                .map(|(i, x)| (x, Location::Atom(i)))
+                .chain(
+                    (self.negated_atoms.iter().enumerate())
+                        .map(|(i, x)| (x, Location::NegatedAtom(i))),
+                );
+            for (atom, location) in atoms {
+                match schema.arity(&atom.relation_name) {
+                    None => result.push(Diagnostic::new(
+                        DiagnosticKind::UnknownRelation(atom.relation_name.clone()),
+                        location,
+                    )),
+                    Some(arity) if arity != atom.terms.len() => result.push(Diagnostic::new(
+                        DiagnosticKind::ArityMismatch {
+                            relation: atom.relation_name.clone(),
+                            expected: arity,
+                            found: atom.terms.len(),
+                        },
+                        location,
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        for (i, atom) in self.atoms_list.iter().enumerate() {
+            if self.atoms_list[..i]
+                .iter()
+                .any(|x| x.relation_name == atom.relation_name && x.terms == atom.terms)
+            {
+                result.push(Diagnostic::new(
+                    DiagnosticKind::DuplicateAtom(atom.to_string()),
+                    Location::Atom(i),
+                ));
+            }
+        }
+        result
+    }
+
+    pub fn from_rule(rule: &RuleNode) -> ConjunctiveQuery {
         let mut result = ConjunctiveQuery {
             atoms_list: Vec::new(),
-            query_name: String::from("Tmp"),
+            negated_atoms: Vec::new(),
+            comparisons: Vec::new(),
+            query_name: rule.head.name.clone(),
             head_variables: Vec::new(),
-            is_boolean: true,
+            head_aggregates: Vec::new(),
+            is_boolean: rule.head.terms.is_empty() && rule.head_aggregates.is_empty(),
         };
-        let name = "[a-zA-Z_][a-zA-Z_0-9]*";
-        let name_list = format!("({name}(?:, {name})*)");
-        let mut re = Regex::new(
-            format!(
-                "{name}\\({name_list}?\\) :- {name}\\({name_list}\\)(, {name}\\({name_list}\\))*"
-            )
-            .as_str(),
-        )
-        .expect("Something went wrong when compiling the regex!");
-        if re.is_match(text_description) {
-            re = Regex::new(format!("({name})\\({name_list}?\\)").as_str())
-                .expect("Something went wrong compiling the regex!");
-            let mut iterator = re.captures_iter(text_description);
-            let head = iterator.next().expect("Boh");
-            result.query_name = String::from(&head[1]);
-            if head.get(2).is_some() {
-                result.is_boolean = false;
-                for variable in (&head[2]).split(',') {
-                    result.head_variables.push(String::from(variable.trim()));
-                }
+        for term in &rule.head.terms {
+            if let Term::Variable(variable) = &term.term {
+                result.head_variables.push(variable.clone());
             }
-            for group in iterator {
-                let mut atom = Atom::new();
-                atom.name = String::from(&group[1]);
-                for variable in (&group[2]).split(',') {
-                    atom.variables.push(String::from(variable.trim()));
-                }
-                result.atoms_list.push(atom);
+        }
+        for aggregate in &rule.head_aggregates {
+            result.head_aggregates.push((
+                aggregate.position,
+                Aggregate::new(aggregate.function, aggregate.variable.clone()),
+            ));
+        }
+        // Positive and negated atoms are named together, so that every atom has its own name
+        let atom_nodes: Vec<&AtomNode> = rule.body.iter().chain(&rule.negated_body).collect();
+        let mut atoms: Vec<Atom> = Vec::new();
+        for atom_node in &atom_nodes {
+            let mut atom = Atom::new();
+            atom.relation_name = atom_node.name.clone();
+            for term in &atom_node.terms {
+                atom.terms.push(term.term.clone());
             }
-        } else {
-            panic!("Error! The input is not a conjunctive query!");
+            atoms.push(atom);
+        }
+        ConjunctiveQuery::name_atoms(&mut atoms);
+        result.negated_atoms = atoms.split_off(rule.body.len());
+        result.atoms_list = atoms;
+        for comparison in &rule.comparisons {
+            result.comparisons.push(Comparison::new(
+                comparison.left.term.clone(),
+                comparison.operator,
+                comparison.right.term.clone(),
+            ));
         }
         result
     }
 
+    // Name every atom after its relation, numbering the atoms of relations occurring more than once
+    fn name_atoms(atoms: &mut [Atom]) {
+        for i in 0..atoms.len() {
+            let relation_name = atoms[i].relation_name.clone();
+            let occurrences = atoms
+                .iter()
+                .filter(|x| x.relation_name == relation_name)
+                .count();
+            atoms[i].name = if occurrences == 1 {
+                relation_name
+            } else {
+                let k = atoms[..i]
+                    .iter()
+                    .filter(|x| x.relation_name == relation_name)
+                    .count()
+                    + 1;
+                format!("{}#{}", relation_name, k)
+            };
+        }
+    }
+
     pub fn atoms(&self) -> Vec<Atom> {
         self.atoms_list.clone()
     }
 
+    pub fn negated_atoms(&self) -> Vec<Atom> {
+        self.negated_atoms.clone()
+    }
+
+    pub fn comparisons(&self) -> Vec<Comparison> {
+        self.comparisons.clone()
+    }
+
     pub fn var(&self) -> HashSet<String> {
         let mut result = HashSet::new();
         for atom in &self.atoms_list {
-            for variable in &atom.variables {
-                result.insert(variable.clone());
+            for variable in atom.get_variables() {
+                result.insert(variable);
             }
         }
         result
     }
 
+    pub fn get_name(&self) -> String {
+        self.query_name.clone()
+    }
+
+    // Head variables, that are the group by variables of an aggregate query
     pub fn head(&self) -> Vec<String> {
         return self.head_variables.clone();
     }
 
+    pub fn is_boolean(&self) -> bool {
+        self.is_boolean
+    }
+
+    pub fn aggregates(&self) -> Vec<(usize, Aggregate)> {
+        self.head_aggregates.clone()
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        !self.head_aggregates.is_empty()
+    }
+
+    // Names of the columns of the query result, head variables and aggregates in the order they appear in the head
+    pub fn head_attributes(&self) -> Vec<String> {
+        let mut result = self.head_variables.clone();
+        for (position, aggregate) in &self.head_aggregates {
+            result.insert(*position, aggregate.to_string());
+        }
+        result
+    }
+
     pub fn generate_random_data(&self, database_file: &mut File) {
         let err_msg = "Error writing on the file!";
+        let mut generated_relations = HashSet::new();
         for atom in &self.atoms_list {
-            writeln!(database_file, "{}", atom.to_string()).expect(err_msg);
-            let n_columns = atom.variables.len();
+            // Relations used by more than one atom are generated only once
+            if !generated_relations.insert(atom.relation_name.clone()) {
+                continue;
+            }
+            // Relations are declared by arity only, so that the data doesn't depend on the variable names of the query
+            writeln!(database_file, "{}/{}", atom.relation_name, atom.terms.len()).expect(err_msg);
+            let n_columns = atom.terms.len();
             let mut rng = rand::thread_rng();
             for _ in 0..rng.gen_range(0, N_MAX_RECORDS) {
                 for _ in 0..(n_columns - 1) {
@@ -169,16 +803,60 @@ impl ConjunctiveQuery {
             print!("{} ", variable);
         }
         println!();
+        if self.is_aggregate() {
+            print!("Aggregates: ");
+            for (_, aggregate) in &self.head_aggregates {
+                print!("{} ", aggregate);
+            }
+            println!();
+        }
         println!("Is query boolean? {}", self.is_boolean);
         println!("Number of atoms: {}", self.atoms_list.len());
         println!("Atoms: ");
         for atom in &self.atoms_list {
             print!("name: {} ", atom.name);
-            print!("variables: ");
-            for variable in &atom.variables {
-                print!("{} ", variable);
+            if atom.name != atom.relation_name {
+                print!("relation: {} ", atom.relation_name);
+            }
+            print!("terms: ");
+            for term in &atom.terms {
+                print!("{} ", term);
             }
             println!();
         }
+        if !self.negated_atoms.is_empty() {
+            println!("Negated atoms: ");
+            for atom in &self.negated_atoms {
+                println!("{}", atom);
+            }
+        }
+        if !self.comparisons.is_empty() {
+            println!("Comparisons: ");
+            for comparison in &self.comparisons {
+                println!("{}", comparison);
+            }
+        }
+    }
+}
+
+impl Display for ConjunctiveQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}({}) :- ",
+            self.query_name,
+            self.head_attributes().join(", ")
+        )?;
+        let mut body = Vec::new();
+        for atom in &self.atoms_list {
+            body.push(atom.to_string());
+        }
+        for atom in &self.negated_atoms {
+            body.push(format!("not {}", atom));
+        }
+        for comparison in &self.comparisons {
+            body.push(comparison.to_string());
+        }
+        write!(f, "{}", body.join(", "))
     }
 }
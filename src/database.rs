@@ -8,24 +8,62 @@ use std::{
     str::FromStr,
 };
 
-use crate::{conjunctive_query::ConjunctiveQuery, join_forest::JoinForest};
+use crate::{
+    agm::AgmBound,
+    conjunctive_query::{
+        AggregateFunction, Atom, Comparison, ComparisonOperator, ConjunctiveQuery, Term,
+    },
+    hyper_graph::HyperGraph,
+    hypertree::Decomposition,
+    program::Program,
+    schema::Schema,
+    trie::{self, Trie},
+    union_query::UnionQuery,
+};
 
 const DEBUG: bool = false;
 const QUERY_RESULT: &'static str = "Query result";
 
 #[derive(Clone)]
-struct Table<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> {
+struct Table<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> {
     name: String,
     attributes: Vec<String>,
     records: Vec<Vec<T>>,
 }
 
+// Partial result of an aggregate of the head over the join tuples represented by a record
+#[derive(Clone)]
+enum PartialAggregate<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> {
+    // count(*) and count(x) only depend on the number of join tuples
+    Count,
+    Sum(i128),
+    Min(Option<T>),
+    Max(Option<T>),
+}
+
+// Number of join tuples represented by a record, together with the partial aggregates over them
+#[derive(Clone)]
+struct Annotation<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> {
+    count: i128,
+    aggregates: Vec<PartialAggregate<T>>,
+}
+
+/*
+    Table whose records stand for groups of join tuples, used to evaluate aggregates without materialising the full join.
+    Records are always distinct, their annotations are added up when records are grouped together.
+*/
+struct AnnotatedTable<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> {
+    name: String,
+    attributes: Vec<String>,
+    records: Vec<(Vec<T>, Annotation<T>)>,
+}
+
 #[derive(Clone)]
-pub struct Database<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> {
+pub struct Database<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> {
     tables: HashMap<String, Table<T>>,
 }
 
-impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Table<T> {
+impl<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> Table<T> {
     pub fn new(name: String) -> Table<T> {
         Table {
             name: name,
@@ -34,25 +72,88 @@ impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Table<T> {
         }
     }
 
-    // Not used in the current implementation
-    /*pub fn select(&self, attribute: &String, value: T) -> Vec<Vec<T>> {
-        let attribute_index = self
-            .attributes
-            .iter()
-            .position(|x| x == attribute)
-            .expect(format!("Attribute {} not in table {}", attribute, self.name).as_str());
-        let mut result_tmp = HashSet::new();
+    pub fn select(&self, attribute_index: usize, value: T) -> Vec<Vec<T>> {
+        let mut result = Vec::new();
         for record in &self.records {
             if record[attribute_index] == value {
-                result_tmp.insert(record.clone());
+                result.push(record.clone());
+            }
+        }
+        result
+    }
+
+    // Keep only the records having the same value in the two given columns
+    pub fn select_equal(&self, attribute_index1: usize, attribute_index2: usize) -> Vec<Vec<T>> {
+        let mut result = Vec::new();
+        for record in &self.records {
+            if record[attribute_index1] == record[attribute_index2] {
+                result.push(record.clone());
             }
         }
+        result
+    }
+
+    // Column index of a variable or parsed value of a constant, that is None if the constant is not in the domain of T
+    fn operand(&self, term: &Term) -> (Option<usize>, Option<T>) {
+        match term {
+            Term::Variable(variable) => (
+                Some(
+                    self.attributes
+                        .iter()
+                        .position(|x| x == variable)
+                        .unwrap_or_else(|| {
+                            panic!("Attribute {} not in table {}", variable, self.name)
+                        }),
+                ),
+                None,
+            ),
+            Term::Constant(constant) => (None, constant.value().parse::<T>().ok()),
+        }
+    }
+
+    pub fn select_comparison(&self, comparison: &Comparison) -> Vec<Vec<T>> {
+        let (left_index, left_value) = self.operand(&comparison.get_left());
+        let (right_index, right_value) = self.operand(&comparison.get_right());
+        let operator = comparison.get_operator();
         let mut result = Vec::new();
-        for record in result_tmp {
-            result.push(record.clone());
+        for record in &self.records {
+            let left = left_index.map(|i| record[i]).or(left_value);
+            let right = right_index.map(|i| record[i]).or(right_value);
+            let holds = match (left, right) {
+                (Some(left), Some(right)) => operator.compare(&left, &right),
+                // A constant not in the domain of T is different from any value
+                _ => operator == ComparisonOperator::NotEqual,
+            };
+            if holds {
+                result.push(record.clone());
+            }
+        }
+        result
+    }
+
+    // Keep only the records that have no matching record in the other table, whose attributes must all belong to this table
+    pub fn anti_join(&self, other_table: &Table<T>) -> Vec<Vec<T>> {
+        let mut attributes_indexes = Vec::new();
+        for attribute in &other_table.attributes {
+            attributes_indexes.push(
+                self.attributes
+                    .iter()
+                    .position(|x| x == attribute)
+                    .unwrap_or_else(|| {
+                        panic!("Attribute {} not in table {}", attribute, self.name)
+                    }),
+            );
+        }
+        let other_records: HashSet<&Vec<T>> = other_table.records.iter().collect();
+        let mut result = Vec::new();
+        for record in &self.records {
+            let key: Vec<T> = attributes_indexes.iter().map(|x| record[*x]).collect();
+            if !other_records.contains(&key) {
+                result.push(record.clone());
+            }
         }
         result
-    }*/
+    }
 
     pub fn project(&self, attributes: Vec<String>) -> Vec<Vec<T>> {
         // Retrieve all attribute indexes in the table
@@ -65,10 +166,14 @@ impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Table<T> {
                     .expect(format!("Attribute {} not in table {}", attribute, self.name).as_str()),
             );
         }
+        self.project_indexes(&attributes_indexes)
+    }
+
+    pub fn project_indexes(&self, attributes_indexes: &[usize]) -> Vec<Vec<T>> {
         let mut result_tmp = HashSet::new();
         for record in &self.records {
             let mut record_with_projection = Vec::new();
-            for index in &attributes_indexes {
+            for index in attributes_indexes {
                 record_with_projection.push(record[*index]);
             }
             result_tmp.insert(record_with_projection);
@@ -166,6 +271,11 @@ impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Table<T> {
 
     pub fn print(&self) {
         println!("Table name: {}", self.name);
+        if self.attributes.is_empty() {
+            // A table without attributes is the result of a boolean query
+            println!("Result: {}", !self.records.is_empty());
+            return;
+        }
         print!("Attributes: ");
         let mut i = 0;
         for attribute in &self.attributes {
@@ -192,7 +302,242 @@ impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Table<T> {
     }
 }
 
-impl<T: Display + Hash + PartialEq + Eq + Clone + FromStr + Copy> Database<T>
+impl<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> Annotation<T> {
+    // Annotation of the join of two records: every tuple of one side is combined with every tuple of the other one
+    fn join(&self, other: &Annotation<T>) -> Annotation<T> {
+        let mut aggregates = Vec::new();
+        for pair in self.aggregates.iter().zip(&other.aggregates) {
+            aggregates.push(match pair {
+                (PartialAggregate::Sum(a), PartialAggregate::Sum(b)) => {
+                    PartialAggregate::Sum(a * other.count + b * self.count)
+                }
+                (PartialAggregate::Min(a), PartialAggregate::Min(b)) => {
+                    PartialAggregate::Min(Annotation::combine(*a, *b, std::cmp::min))
+                }
+                (PartialAggregate::Max(a), PartialAggregate::Max(b)) => {
+                    PartialAggregate::Max(Annotation::combine(*a, *b, std::cmp::max))
+                }
+                (aggregate, _) => aggregate.clone(),
+            });
+        }
+        Annotation {
+            count: self.count * other.count,
+            aggregates,
+        }
+    }
+
+    // Annotation of the union of two disjoint groups of tuples
+    fn add(&mut self, other: &Annotation<T>) {
+        self.count += other.count;
+        for pair in self.aggregates.iter_mut().zip(&other.aggregates) {
+            match pair {
+                (PartialAggregate::Sum(a), PartialAggregate::Sum(b)) => *a += b,
+                (PartialAggregate::Min(a), PartialAggregate::Min(b)) => {
+                    *a = Annotation::combine(*a, *b, std::cmp::min)
+                }
+                (PartialAggregate::Max(a), PartialAggregate::Max(b)) => {
+                    *a = Annotation::combine(*a, *b, std::cmp::max)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn combine(a: Option<T>, b: Option<T>, f: fn(T, T) -> T) -> Option<T> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(f(a, b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> AnnotatedTable<T> {
+    /*
+        Annotate every record of the table of an atom as a single join tuple.
+        Each aggregated variable is owned by the first atom containing it, the other atoms get the neutral element,
+        so that the value of a variable shared by many atoms is aggregated once for every join tuple.
+    */
+    fn new(table: &Table<T>, cq: &ConjunctiveQuery) -> AnnotatedTable<T> {
+        let mut owned_indexes = Vec::new();
+        for (_, aggregate) in &cq.aggregates() {
+            let owned_index = aggregate.get_variable().and_then(|variable| {
+                let owner = cq
+                    .atoms()
+                    .into_iter()
+                    .find(|x| x.get_variables().contains(&variable))?;
+                if owner.get_name() != table.name {
+                    return None;
+                }
+                table.attributes.iter().position(|x| *x == variable)
+            });
+            owned_indexes.push((aggregate.get_function(), owned_index));
+        }
+        AnnotatedTable::annotate(table, &owned_indexes)
+    }
+
+    // Annotate every record of a table of whole join tuples, that owns all the aggregated variables
+    fn from_join_tuples(table: &Table<T>, cq: &ConjunctiveQuery) -> AnnotatedTable<T> {
+        let mut owned_indexes = Vec::new();
+        for (_, aggregate) in &cq.aggregates() {
+            let owned_index = aggregate
+                .get_variable()
+                .and_then(|variable| table.attributes.iter().position(|x| *x == variable));
+            owned_indexes.push((aggregate.get_function(), owned_index));
+        }
+        AnnotatedTable::annotate(table, &owned_indexes)
+    }
+
+    // Annotated table counting every record once, with the aggregated values at the owned indexes and the neutral element for the other aggregates
+    fn annotate(
+        table: &Table<T>,
+        owned_indexes: &[(AggregateFunction, Option<usize>)],
+    ) -> AnnotatedTable<T> {
+        let mut result = AnnotatedTable {
+            name: table.name.clone(),
+            attributes: table.attributes.clone(),
+            records: Vec::new(),
+        };
+        for record in &table.records {
+            let mut aggregates = Vec::new();
+            for (function, owned_index) in owned_indexes {
+                let value = owned_index.map(|i| record[i]);
+                aggregates.push(match function {
+                    AggregateFunction::Count => PartialAggregate::Count,
+                    AggregateFunction::Sum => PartialAggregate::Sum(value.map_or(0, |x| {
+                        x.to_string()
+                            .parse::<i128>()
+                            .unwrap_or_else(|_| panic!("Cannot sum the non-numeric value {}", x))
+                    })),
+                    AggregateFunction::Min => PartialAggregate::Min(value),
+                    AggregateFunction::Max => PartialAggregate::Max(value),
+                });
+            }
+            result.records.push((
+                record.clone(),
+                Annotation {
+                    count: 1,
+                    aggregates,
+                },
+            ));
+        }
+        // Stored relations are sets, duplicate records stand for the same tuple
+        let mut records = HashMap::new();
+        for (record, annotation) in result.records {
+            records.entry(record).or_insert(annotation);
+        }
+        result.records = records.into_iter().collect();
+        result
+    }
+
+    // Project onto the given attributes, adding up the annotations of the records that become equal
+    fn group(&self, attributes: &[String]) -> AnnotatedTable<T> {
+        let mut attributes_indexes = Vec::new();
+        for attribute in attributes {
+            attributes_indexes.push(
+                self.attributes
+                    .iter()
+                    .position(|x| x == attribute)
+                    .unwrap_or_else(|| {
+                        panic!("Attribute {} not in table {}", attribute, self.name)
+                    }),
+            );
+        }
+        let mut groups: HashMap<Vec<T>, Annotation<T>> = HashMap::new();
+        for (record, annotation) in &self.records {
+            let key: Vec<T> = attributes_indexes.iter().map(|x| record[*x]).collect();
+            match groups.get_mut(&key) {
+                Some(group) => group.add(annotation),
+                None => {
+                    groups.insert(key, annotation.clone());
+                }
+            }
+        }
+        AnnotatedTable {
+            name: self.name.clone(),
+            attributes: attributes.to_vec(),
+            records: groups.into_iter().collect(),
+        }
+    }
+
+    // Hash join whose result has the attributes of this table followed by the other ones of the other table
+    fn join(&self, other_table: &AnnotatedTable<T>) -> AnnotatedTable<T> {
+        let mut result = AnnotatedTable {
+            name: self.name.clone(),
+            attributes: self.attributes.clone(),
+            records: Vec::new(),
+        };
+        let mut common_indexes = Vec::new();
+        let mut other_indexes = Vec::new();
+        for (i, attribute) in other_table.attributes.iter().enumerate() {
+            match self.attributes.iter().position(|x| x == attribute) {
+                Some(j) => common_indexes.push((j, i)),
+                None => {
+                    other_indexes.push(i);
+                    result.attributes.push(attribute.clone());
+                }
+            }
+        }
+        let mut hash_table: HashMap<Vec<T>, Vec<usize>> = HashMap::new();
+        for (i, (record, _)) in other_table.records.iter().enumerate() {
+            let key = common_indexes.iter().map(|(_, j)| record[*j]).collect();
+            hash_table.entry(key).or_default().push(i);
+        }
+        for (record, annotation) in &self.records {
+            let key: Vec<T> = common_indexes.iter().map(|(j, _)| record[*j]).collect();
+            for i in hash_table.get(&key).into_iter().flatten() {
+                let (other_record, other_annotation) = &other_table.records[*i];
+                let mut join_record = record.clone();
+                join_record.extend(other_indexes.iter().map(|j| other_record[*j]));
+                result
+                    .records
+                    .push((join_record, annotation.join(other_annotation)));
+            }
+        }
+        result
+    }
+
+    // Keep only the records selected by a filter on the plain table
+    fn filter(&mut self, filter: impl Fn(&Table<T>) -> Vec<Vec<T>>) {
+        let mut table = Table::new(self.name.clone());
+        table.attributes = self.attributes.clone();
+        table.records = self.records.iter().map(|x| x.0.clone()).collect();
+        let kept: HashSet<Vec<T>> = filter(&table).into_iter().collect();
+        self.records.retain(|x| kept.contains(&x.0));
+    }
+
+    // Turn the groups of the head variables into the query result, placing the aggregates at their positions in the head
+    fn aggregate_result(&self, cq: &ConjunctiveQuery) -> Table<T> {
+        let grouped = self.group(&cq.head());
+        let mut result = Table::new(self.name.clone());
+        result.attributes = cq.head_attributes();
+        for (key, annotation) in &grouped.records {
+            let mut record = key.clone();
+            for ((position, _), aggregate) in cq.aggregates().iter().zip(&annotation.aggregates) {
+                let value = match aggregate {
+                    PartialAggregate::Count => annotation.count.to_string(),
+                    PartialAggregate::Sum(sum) => sum.to_string(),
+                    PartialAggregate::Min(Some(value)) | PartialAggregate::Max(Some(value)) => {
+                        value.to_string()
+                    }
+                    _ => panic!("Aggregated variable not bound in table {}", self.name),
+                };
+                record.insert(
+                    *position,
+                    value.parse::<T>().unwrap_or_else(|_| {
+                        panic!(
+                            "Aggregate value {} not representable in the database",
+                            value
+                        )
+                    }),
+                );
+            }
+            result.records.push(record);
+        }
+        result
+    }
+}
+
+impl<T: Display + Hash + PartialEq + Eq + Ord + Clone + FromStr + Copy> Database<T>
 where
     <T as FromStr>::Err: Debug,
 {
@@ -209,12 +554,23 @@ where
             .split('\n') /*.map(|x| String::from(x))*/
             .collect();
         let name = "[a-zA-Z_][a-zA-Z_0-9]*";
-        let name_list = format!("({name}(?:, {name})*)");
-        let re = Regex::new(format!("({name})\\( {name_list}? \\)").as_str())
+        let name_list = format!("({name}(?:\\s*,\\s*{name})*)");
+        let re = Regex::new(format!("({name})\\(\\s*{name_list}?\\s*\\)").as_str())
+            .expect("Something went wrong compiling the regex!");
+        // Relations can also be declared with their arity only (e.g. R/3), in that case the columns are named after their position
+        let re_positional = Regex::new(format!("^({name})\\s*/\\s*([0-9]+)$").as_str())
             .expect("Something went wrong compiling the regex!");
         let mut current_table = String::from("");
         for line in database_lines {
-            if re.is_match(line) {
+            if let Some(group) = re_positional.captures(line.trim()) {
+                let mut table = Table::new(String::from(&group[1]));
+                let arity: usize = group[2].parse().expect("Error parsing the file!");
+                for i in 0..arity {
+                    table.attributes.push(format!("c{}", i));
+                }
+                current_table = table.name.clone();
+                result.tables.insert(table.name.clone(), table);
+            } else if re.is_match(line) {
                 for group in re.captures_iter(line) {
                     let mut table = Table::new(String::from(&group[1]));
                     for attribute in (&group[2]).split(',') {
@@ -235,97 +591,669 @@ where
         result
     }
 
+    // Relations stored in the database, without the query results
+    pub fn schema(&self) -> Schema {
+        let mut result = Schema::new();
+        let mut names: Vec<&String> = self
+            .tables
+            .keys()
+            .filter(|x| !x.starts_with(QUERY_RESULT))
+            .collect();
+        names.sort();
+        for name in names {
+            result.add_relation(name, self.tables[name].attributes.clone());
+        }
+        result
+    }
+
+    /*
+        AGM bound on the number of join tuples of the query, predicted before evaluating it from the cardinalities of the tables of its atoms
+        once their constants, repeated and anonymous variables are applied, that are at most the ones of the stored relations.
+    */
+    pub fn agm_bound(&self, cq: &ConjunctiveQuery) -> AgmBound {
+        let mut atom_tables = self.bind_atoms(&cq.atoms());
+        Database::project_anonymous_variables(&cq.atoms(), &mut atom_tables);
+        let atoms: Vec<String> = cq.atoms().iter().map(|x| x.get_name()).collect();
+        let cardinalities: Vec<usize> =
+            atoms.iter().map(|x| atom_tables[x].records.len()).collect();
+        AgmBound::new(&HyperGraph::new(cq), &atoms, &cardinalities)
+    }
+
+    fn bind_atoms(&self, atoms: &[Atom]) -> HashMap<String, Table<T>> {
+        let mut atom_tables = HashMap::new();
+        for atom in atoms {
+            let table = self
+                .tables
+                .get(&atom.get_relation_name())
+                .unwrap_or_else(|| panic!("Table {} not in database", atom.get_relation_name()));
+            atom_tables.insert(atom.get_name(), Database::bind_atom(atom, table));
+        }
+        atom_tables
+    }
+
+    // Project away the columns of the anonymous variables, that join with nothing, before the tables of the atoms are joined
+    fn project_anonymous_variables(atoms: &[Atom], atom_tables: &mut HashMap<String, Table<T>>) {
+        for atom in atoms {
+            if atom.has_anonymous_variables() {
+                let table = atom_tables.get_mut(&atom.get_name()).unwrap();
+                table.records = table.project(atom.get_variables());
+                table.attributes = atom.get_variables();
+            }
+        }
+    }
+
+    fn bind_atom(atom: &Atom, table: &Table<T>) -> Table<T> {
+        /*
+            Build the table of an atom out of the table of its relation, naming the columns after the variables of the atom by position,
+            so that the stored relations don't depend on the query and atoms over the same relation (self-joins) get their own variable bindings.
+            Constants and repeated variables are pushed down as selections: a constant selects the records having that value in its column,
+            a repeated variable selects the records having the same value in all its columns.
+            Only the first column of each variable is kept, so that the resulting table has one attribute for each distinct variable of the atom,
+            anonymous ones included: they are projected away by the caller, since aggregates still need them to count the records.
+        */
+        if table.attributes.len() != atom.get_terms().len() {
+            panic!(
+                "Atom {} has {} terms, but table {} has {} attributes",
+                atom,
+                atom.get_terms().len(),
+                table.name,
+                table.attributes.len()
+            );
+        }
+        let mut result_tmp = Table::new(atom.get_name());
+        result_tmp.records = table.records.clone();
+        let mut variables: Vec<String> = Vec::new();
+        let mut variables_indexes: Vec<usize> = Vec::new();
+        for (i, term) in atom.get_terms().iter().enumerate() {
+            match term {
+                Term::Variable(variable) => match variables.iter().position(|x| x == variable) {
+                    Some(j) => {
+                        result_tmp.records = result_tmp.select_equal(variables_indexes[j], i)
+                    }
+                    None => {
+                        variables.push(variable.clone());
+                        variables_indexes.push(i);
+                    }
+                },
+                // A constant that can't be represented in the database type matches no record
+                Term::Constant(constant) => match constant.value().parse::<T>() {
+                    Ok(value) => result_tmp.records = result_tmp.select(i, value),
+                    Err(_) => result_tmp.records.clear(),
+                },
+            }
+        }
+        if atom.has_constants() || atom.has_repeated_variables() {
+            result_tmp.records = result_tmp.project_indexes(&variables_indexes);
+        }
+        result_tmp.attributes = variables;
+        result_tmp
+    }
+
+    fn apply_local_comparisons(
+        cq: &ConjunctiveQuery,
+        atom_tables: &mut HashMap<String, Table<T>>,
+    ) -> Vec<Comparison> {
+        // Evaluate as a selection every comparison whose variables all belong to one atom, returning the ones left to be evaluated as post-filters
+        let mut post_filters = Vec::new();
+        for comparison in &cq.comparisons() {
+            let variables = comparison.get_variables();
+            let atom = cq
+                .atoms()
+                .into_iter()
+                .find(|x| variables.iter().all(|v| x.get_variables().contains(v)));
+            match atom {
+                Some(atom) => {
+                    let table = atom_tables.get_mut(&atom.get_name()).unwrap();
+                    table.records = table.select_comparison(comparison);
+                }
+                None => post_filters.push(comparison.clone()),
+            }
+        }
+        post_filters
+    }
+
+    fn apply_local_negations(
+        cq: &ConjunctiveQuery,
+        atom_tables: &mut HashMap<String, Table<T>>,
+        negated_tables: &HashMap<String, Table<T>>,
+    ) -> Vec<Atom> {
+        /*
+            Attach every negated atom to a join-tree node covering all its variables, evaluating it as an anti-join on the node table.
+            The negated atoms not covered by a single node are returned, to be evaluated as anti-joins on the join result.
+        */
+        let mut post_filters = Vec::new();
+        for negated_atom in &cq.negated_atoms() {
+            let variables = negated_atom.get_variables();
+            let atom = cq
+                .atoms()
+                .into_iter()
+                .find(|x| variables.iter().all(|v| x.get_variables().contains(v)));
+            match atom {
+                Some(atom) => {
+                    let table = atom_tables.get_mut(&atom.get_name()).unwrap();
+                    table.records =
+                        table.anti_join(negated_tables.get(&negated_atom.get_name()).unwrap());
+                }
+                None => post_filters.push(negated_atom.clone()),
+            }
+        }
+        post_filters
+    }
+
     pub fn yannakakis(&mut self, cq: &ConjunctiveQuery) {
-        // Simplified version of origina Yannakakis algorithm
-        for root in &JoinForest::new(cq).get_roots() {
-            let post_order_tree = root.borrow().post_order_wrapper();
+        let mut result = self.evaluate_yannakakis(cq);
+        result.name = format!("{} {}", QUERY_RESULT, cq.get_name());
+        self.tables.insert(result.name.clone(), result);
+    }
+
+    pub fn yannakakis_union(&mut self, uq: &UnionQuery) {
+        // Evaluate every disjunct on its own (they are alpha-acyclic by construction), the union is computed by position because disjuncts can name their head variables differently
+        let mut result = Table::new(format!("{} {}", QUERY_RESULT, uq.get_name()));
+        let mut records = HashSet::new();
+        for (i, cq) in uq.disjuncts().iter().enumerate() {
+            let disjunct_result = self.evaluate_yannakakis(cq);
+            if i == 0 {
+                result.attributes = disjunct_result.attributes;
+            }
+            records.extend(disjunct_result.records);
+        }
+        result.records = records.into_iter().collect();
+        self.tables.insert(result.name.clone(), result);
+    }
+
+    // Answer any query, cyclic ones included, through a hypertree decomposition of its hypergraph
+    pub fn yannakakis_decomposition(
+        &mut self,
+        cq: &ConjunctiveQuery,
+        decomposition: &Decomposition,
+    ) {
+        let mut result = self.evaluate_decomposition(cq, decomposition);
+        result.name = format!("{} {}", QUERY_RESULT, cq.get_name());
+        self.tables.insert(result.name.clone(), result);
+    }
 
-            for r in &post_order_tree {
-                if DEBUG {
-                    println!("R: {}", &r.borrow().get_relation_name());
+    // Answer any query with a worst-case optimal join of all its atoms at once, that avoids the blow-up of pairwise joins on cyclic queries
+    pub fn generic_join(&mut self, cq: &ConjunctiveQuery) {
+        let mut result = self.evaluate_generic_join(cq);
+        // Named apart from the result of Yannakakis algorithm, so that both can be kept and compared
+        result.name = format!("{} {} (generic join)", QUERY_RESULT, cq.get_name());
+        self.tables.insert(result.name.clone(), result);
+    }
+
+    fn evaluate_yannakakis(&self, cq: &ConjunctiveQuery) -> Table<T> {
+        // Stored relations are only read, so that the same database can answer many different queries
+        let mut atom_tables = self.bind_atoms(&cq.atoms());
+        let mut negated_tables = self.bind_atoms(&cq.negated_atoms());
+        Database::project_anonymous_variables(&cq.negated_atoms(), &mut negated_tables);
+        let post_filters = Database::apply_local_comparisons(cq, &mut atom_tables);
+        let negated_post_filters =
+            Database::apply_local_negations(cq, &mut atom_tables, &negated_tables);
+        // Variables of the post-filters are kept in the join-tree passes like the head ones, and projected away after filtering
+        let mut variables = cq.head();
+        let post_filters_variables = post_filters
+            .iter()
+            .flat_map(|x| x.get_variables())
+            .chain(negated_post_filters.iter().flat_map(|x| x.get_variables()));
+        for variable in post_filters_variables {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+        // Aggregates group the anonymous variables away in the first pass, adding up the records they tell apart
+        if cq.is_aggregate() {
+            let mut result = Database::aggregate_join_tree_passes(cq, &variables, &atom_tables);
+            for comparison in &post_filters {
+                result.filter(|x| x.select_comparison(comparison));
+            }
+            for atom in &negated_post_filters {
+                result.filter(|x| x.anti_join(negated_tables.get(&atom.get_name()).unwrap()));
+            }
+            return result.aggregate_result(cq);
+        }
+        Database::project_anonymous_variables(&cq.atoms(), &mut atom_tables);
+        let mut result = Database::join_tree_passes(cq, &variables, atom_tables);
+        for comparison in &post_filters {
+            result.records = result.select_comparison(comparison);
+        }
+        for atom in &negated_post_filters {
+            result.records = result.anti_join(negated_tables.get(&atom.get_name()).unwrap());
+        }
+        result.records = result.project(cq.head());
+        result.attributes = cq.head();
+        result
+    }
+
+    fn join_tree_passes(
+        cq: &ConjunctiveQuery,
+        head: &[String],
+        mut atom_tables: HashMap<String, Table<T>>,
+    ) -> Table<T> {
+        // Simplified version of origina Yannakakis algorithm: every atom is joined into its parent, children before parents
+        let atoms = cq.atoms();
+        let join_tree = HyperGraph::new(cq)
+            .join_tree()
+            .expect("The query is not alpha-acyclic!");
+        for i in join_tree.order().iter().rev() {
+            let Some(p) = join_tree.parent(*i) else {
+                continue;
+            };
+            let r = &atoms[p];
+            let s = &atoms[*i];
+            if DEBUG {
+                println!("R: {}", r.get_name());
+                println!("S: {}", s.get_name());
+            }
+            let tmp_table_r = atom_tables.get(&r.get_name()).unwrap();
+            let tmp_table_s = atom_tables.get(&s.get_name()).unwrap();
+
+            if DEBUG {
+                println!("Join between {} and {}", tmp_table_r.name, tmp_table_s.name);
+            }
+
+            let mut result_tmp = tmp_table_r.natural_join(tmp_table_s);
+
+            if DEBUG {
+                println!("Join result:");
+                result_tmp.print();
+            }
+
+            // The variables of the subtree of s needed above it are in r (running intersection), apart from the head ones
+            let mut projection_variables = r.get_variables();
+            for variable in head {
+                if (tmp_table_r.attributes.contains(variable)
+                    || tmp_table_s.attributes.contains(variable))
+                    && !projection_variables.contains(variable)
+                {
+                    projection_variables.push(variable.clone());
                 }
-                for s in &r.borrow().get_children() {
-                    if DEBUG {
-                        println!("S: {}", &s.borrow().get_relation_name());
-                    }
-                    let tmp_table_r = self.tables.get(&r.borrow().get_relation_name()).unwrap();
-                    let tmp_table_s = self.tables.get(&s.borrow().get_relation_name()).unwrap();
+            }
 
-                    if DEBUG {
-                        println!("Join between {} and {}", tmp_table_r.name, tmp_table_s.name);
-                    }
+            if DEBUG {
+                println!("Projection variables: {}", projection_variables.join(" "));
+            }
 
-                    let mut result_tmp = tmp_table_r.natural_join(tmp_table_s);
+            let tmp = result_tmp.project(projection_variables.clone());
+            result_tmp = Table::new(r.get_name());
+            result_tmp.attributes = projection_variables;
+            result_tmp.records = tmp;
+            atom_tables.insert(result_tmp.name.clone(), result_tmp);
+        }
 
-                    if DEBUG {
-                        println!("Join result:");
-                        result_tmp.print();
-                    }
+        // Keep only the head variables of every tree and combine them with the ones of the other trees of the forest
+        let mut result: Option<Table<T>> = None;
+        for root in &join_tree.roots() {
+            let root_table = atom_tables.remove(&atoms[*root].get_name()).unwrap();
+            let head_variables: Vec<String> = head
+                .iter()
+                .filter(|x| root_table.attributes.contains(x))
+                .cloned()
+                .collect();
+            let mut tree_result = Table::new(atoms[*root].get_name());
+            tree_result.records = root_table.project(head_variables.clone());
+            tree_result.attributes = head_variables;
+            result = match result {
+                None => Some(tree_result),
+                Some(table) => Some(table.natural_join(&tree_result)),
+            };
+        }
+        let result = result.expect("The query has no atoms!");
+        let mut query_result = Table::new(result.name.clone());
+        query_result.records = result.project(head.to_vec());
+        query_result.attributes = head.to_vec();
+        query_result
+    }
 
-                    let mut projection_variables = Vec::new();
-                    for variable in &r.borrow().get_variables() {
-                        if !projection_variables.contains(variable) {
-                            projection_variables.push(variable.clone());
-                        }
-                    }
-                    for variable in &cq.head() {
-                        if (tmp_table_r.attributes.contains(variable)
-                            || tmp_table_s.attributes.contains(variable))
-                            && !projection_variables.contains(variable)
-                        {
-                            projection_variables.push(variable.clone());
-                        }
-                    }
+    /*
+        Same bottom-up traversal of the join tree as join_tree_passes, but on annotated tables:
+        variables not needed above a node are grouped away as soon as possible, adding up the annotations,
+        so that the aggregates are computed without materialising the full join.
+        A group exists only if some join tuple falls into it, as in Datalog (no row for an empty group).
+    */
+    fn aggregate_join_tree_passes(
+        cq: &ConjunctiveQuery,
+        head: &[String],
+        atom_tables: &HashMap<String, Table<T>>,
+    ) -> AnnotatedTable<T> {
+        let mut annotated_tables = HashMap::new();
+        for (name, table) in atom_tables {
+            annotated_tables.insert(name.clone(), AnnotatedTable::new(table, cq));
+        }
+        let atoms = cq.atoms();
+        let join_tree = HyperGraph::new(cq)
+            .join_tree()
+            .expect("The query is not alpha-acyclic!");
+        for i in join_tree.order().iter().rev() {
+            let Some(p) = join_tree.parent(*i) else {
+                continue;
+            };
+            let r_name = atoms[p].get_name();
+            let table_r = annotated_tables.get(&r_name).unwrap();
+            let table_s = annotated_tables.get(&atoms[*i].get_name()).unwrap();
+            // Variables of the subtree of s that occur neither in r nor in the head are not needed anymore
+            let s_variables: Vec<String> = table_s
+                .attributes
+                .iter()
+                .filter(|x| table_r.attributes.contains(x) || head.contains(x))
+                .cloned()
+                .collect();
+            let joined = table_r.join(&table_s.group(&s_variables));
+            let mut projection_variables = atoms[p].get_variables();
+            for variable in head {
+                if joined.attributes.contains(variable) && !projection_variables.contains(variable)
+                {
+                    projection_variables.push(variable.clone());
+                }
+            }
+            let grouped = joined.group(&projection_variables);
+            annotated_tables.insert(r_name, grouped);
+        }
 
-                    if DEBUG {
-                        print!("Projection variables: ");
-                        let mut i = 0;
-                        for variable in &projection_variables {
-                            print!("{}", variable);
-                            if i == (projection_variables.len() - 1) {
-                                print!("\n");
-                            } else {
-                                print!(" ");
-                            }
-                            i += 1;
-                        }
-                    }
+        let mut result: Option<AnnotatedTable<T>> = None;
+        for root in &join_tree.roots() {
+            let root_table = annotated_tables.remove(&atoms[*root].get_name()).unwrap();
+            let head_variables: Vec<String> = head
+                .iter()
+                .filter(|x| root_table.attributes.contains(x))
+                .cloned()
+                .collect();
+            let tree_result = root_table.group(&head_variables);
+            result = match result {
+                None => Some(tree_result),
+                Some(table) => Some(table.join(&tree_result)),
+            };
+        }
+        result.expect("The query has no atoms!").group(head)
+    }
 
-                    let tmp = result_tmp.project(projection_variables.clone());
-
-                    if DEBUG {
-                        println!("Projection result:");
-                        for record in &tmp {
-                            let mut i = 0;
-                            for element in record {
-                                print!("{}", element);
-                                if i == (record.len() - 1) {
-                                    print!("\n");
-                                } else {
-                                    print!(" ");
-                                }
-                                i += 1;
-                            }
-                        }
-                    }
+    /*
+        Evaluate the query over the bags of a hypertree decomposition of its hypergraph, that form an acyclic query of their own.
+        Every bag is materialised by joining the relations of its cover projected onto the bag, then every atom is joined into the first bag
+        containing its variables: the cover only bounds the bag from above, while joining each atom exactly once enforces all of them and keeps
+        the annotations counting the join tuples of the query, so that aggregates work as in the acyclic case.
+        Comparisons and negated atoms not local to an atom are evaluated on a bag covering their variables when there is one.
+        The bag tree is then reduced bottom-up like the join tree in aggregate_join_tree_passes.
+        A query without variables has a decomposition without bags: its atoms are then joined into a single empty bag,
+        that is a boolean check of their tables being non-empty.
+    */
+    fn evaluate_decomposition(
+        &self,
+        cq: &ConjunctiveQuery,
+        decomposition: &Decomposition,
+    ) -> Table<T> {
+        let mut atom_tables = self.bind_atoms(&cq.atoms());
+        let mut negated_tables = self.bind_atoms(&cq.negated_atoms());
+        Database::project_anonymous_variables(&cq.negated_atoms(), &mut negated_tables);
+        let comparisons = Database::apply_local_comparisons(cq, &mut atom_tables);
+        let negated_atoms = Database::apply_local_negations(cq, &mut atom_tables, &negated_tables);
+        // Aggregates group the anonymous variables away when the atoms are joined into the bags, adding up the records they tell apart
+        if !cq.is_aggregate() {
+            Database::project_anonymous_variables(&cq.atoms(), &mut atom_tables);
+        }
+
+        let decomposition = if decomposition.bags().is_empty() {
+            &Decomposition::empty_bag()
+        } else {
+            decomposition
+        };
+        let atoms = cq.atoms();
+        let bags = decomposition.bags();
+        let mut bag_tables = Vec::new();
+        for (i, bag) in bags.iter().enumerate() {
+            let vertices = bag.vertices();
+            let mut cover: Option<Table<T>> = None;
+            for e in bag.cover() {
+                let table = atom_tables.get(&atoms[e].get_name()).unwrap();
+                cover = Some(match cover {
+                    None => table.clone(),
+                    Some(cover) => cover.natural_join(table),
+                });
+            }
+            let mut bag_table = Table::new(format!("bag {}", i));
+            bag_table.records = match cover {
+                Some(cover) => cover.project(vertices.clone()),
+                None => vec![Vec::new()],
+            };
+            bag_table.attributes = vertices;
+            bag_tables.push(AnnotatedTable::new(&bag_table, cq));
+        }
+        for atom in &atoms {
+            let variables = atom.get_variables();
+            let i = (bags.iter())
+                .position(|x| variables.iter().all(|v| x.vertices().contains(v)))
+                .unwrap_or_else(|| panic!("Atom {} is not contained in any bag", atom));
+            let atom_table = AnnotatedTable::new(atom_tables.get(&atom.get_name()).unwrap(), cq);
+            bag_tables[i] = bag_tables[i].join(&atom_table).group(&bags[i].vertices());
+        }
 
-                    result_tmp = Table::new(r.borrow().get_relation_name());
-                    result_tmp.attributes = projection_variables.clone();
-                    result_tmp.records = tmp;
-                    self.tables.insert(result_tmp.name.clone(), result_tmp);
+        let mut post_filters = Vec::new();
+        for comparison in &comparisons {
+            let variables = comparison.get_variables();
+            match (bags.iter()).position(|x| variables.iter().all(|v| x.vertices().contains(v))) {
+                Some(i) => bag_tables[i].filter(|x| x.select_comparison(comparison)),
+                None => post_filters.push(comparison.clone()),
+            }
+        }
+        let mut negated_post_filters = Vec::new();
+        for atom in &negated_atoms {
+            let variables = atom.get_variables();
+            let negated_table = negated_tables.get(&atom.get_name()).unwrap();
+            match (bags.iter()).position(|x| variables.iter().all(|v| x.vertices().contains(v))) {
+                Some(i) => bag_tables[i].filter(|x| x.anti_join(negated_table)),
+                None => negated_post_filters.push(atom.clone()),
+            }
+        }
+        let mut head = cq.head();
+        let post_filters_variables = post_filters
+            .iter()
+            .flat_map(|x| x.get_variables())
+            .chain(negated_post_filters.iter().flat_map(|x| x.get_variables()));
+        for variable in post_filters_variables {
+            if !head.contains(&variable) {
+                head.push(variable);
+            }
+        }
+
+        // Parents come before their children, so that going backwards every bag is complete when it is joined into its parent
+        for i in (0..bags.len()).rev() {
+            let Some(p) = decomposition.parent(i) else {
+                continue;
+            };
+            let parent_variables = bags[p].vertices();
+            let child_variables: Vec<String> = (bag_tables[i].attributes.iter())
+                .filter(|x| parent_variables.contains(x) || head.contains(x))
+                .cloned()
+                .collect();
+            let joined = bag_tables[p].join(&bag_tables[i].group(&child_variables));
+            let mut projection_variables = parent_variables;
+            for variable in &head {
+                if joined.attributes.contains(variable) && !projection_variables.contains(variable)
+                {
+                    projection_variables.push(variable.clone());
                 }
             }
-            let mut tmp = self
-                .tables
-                .get(&root.borrow().get_relation_name())
-                .unwrap()
-                .clone();
-            self.tables.remove(&root.borrow().get_relation_name());
-            tmp.name = format!("{} {}", QUERY_RESULT, &root.borrow().get_relation_name());
-            tmp.attributes = cq.head();
-            tmp.records = tmp.project(cq.head());
-            self.tables.insert(tmp.name.clone(), tmp);
+            bag_tables[p] = joined.group(&projection_variables);
+        }
+        let mut result: Option<AnnotatedTable<T>> = None;
+        for root in decomposition.roots() {
+            let head_variables: Vec<String> = (head.iter())
+                .filter(|x| bag_tables[root].attributes.contains(x))
+                .cloned()
+                .collect();
+            let tree_result = bag_tables[root].group(&head_variables);
+            result = match result {
+                None => Some(tree_result),
+                Some(table) => Some(table.join(&tree_result)),
+            };
+        }
+        let mut result = result.expect("The decomposition has no bags!").group(&head);
+        for comparison in &post_filters {
+            result.filter(|x| x.select_comparison(comparison));
+        }
+        for atom in &negated_post_filters {
+            result.filter(|x| x.anti_join(negated_tables.get(&atom.get_name()).unwrap()));
+        }
+        // Without aggregates the result has the head variables as columns, and the annotations are dropped
+        result.aggregate_result(cq)
+    }
+
+    /*
+        Evaluate the query with Leapfrog Triejoin over the tables of its atoms, binding the variables in the order chosen from its hypergraph.
+        The join enumerates every join tuple, so the remaining filters are applied to it directly, and the aggregates are computed
+        from it keeping the anonymous variables, bound last, that tell apart the join tuples.
+    */
+    fn evaluate_generic_join(&self, cq: &ConjunctiveQuery) -> Table<T> {
+        let mut atom_tables = self.bind_atoms(&cq.atoms());
+        let mut negated_tables = self.bind_atoms(&cq.negated_atoms());
+        Database::project_anonymous_variables(&cq.negated_atoms(), &mut negated_tables);
+        let post_filters = Database::apply_local_comparisons(cq, &mut atom_tables);
+        let negated_post_filters =
+            Database::apply_local_negations(cq, &mut atom_tables, &negated_tables);
+        if !cq.is_aggregate() {
+            Database::project_anonymous_variables(&cq.atoms(), &mut atom_tables);
+        }
+
+        let mut order = HyperGraph::new(cq).variable_order();
+        let mut tables = Vec::new();
+        for atom in &cq.atoms() {
+            let table = atom_tables.get(&atom.get_name()).unwrap();
+            for attribute in &table.attributes {
+                if !order.contains(attribute) {
+                    order.push(attribute.clone());
+                }
+            }
+            tables.push(table);
+        }
+        let tries: Vec<Trie<T>> = (tables.iter())
+            .map(|x| Trie::new(&x.attributes, &x.records, &order))
+            .collect();
+        let mut result = Table::new(cq.get_name());
+        result.records = trie::leapfrog_triejoin(&tries, &order);
+        result.attributes = order;
+        for comparison in &post_filters {
+            result.records = result.select_comparison(comparison);
+        }
+        for atom in &negated_post_filters {
+            result.records = result.anti_join(negated_tables.get(&atom.get_name()).unwrap());
+        }
+        if cq.is_aggregate() {
+            return AnnotatedTable::from_join_tuples(&result, cq).aggregate_result(cq);
+        }
+        result.records = result.project(cq.head());
+        result.attributes = cq.head();
+        result
+    }
+
+    /*
+        Evaluate a Datalog program with semi-naive fixpoint iteration, storing every derived relation in the database with positional columns.
+        After a first naive round, every round evaluates each rule once for every atom over a derived relation, binding that atom to the records
+        found in the previous round (the delta) and the others to the whole relations, so that no derivation from old records only is repeated.
+        Records already stored in a relation with the name of a rule head are kept as facts of that relation.
+    */
+    pub fn evaluate_program(&mut self, program: &Program) {
+        let mut known_records: HashMap<String, HashSet<Vec<T>>> = HashMap::new();
+        for (name, arity) in &program.derived_relations() {
+            let table = self.tables.entry(name.clone()).or_insert_with(|| {
+                let mut table = Table::new(name.clone());
+                table.attributes = (0..*arity).map(|i| format!("c{}", i)).collect();
+                table
+            });
+            if table.attributes.len() != *arity {
+                panic!(
+                    "Relation {} has {} attributes, but the program derives it with arity {}",
+                    name,
+                    table.attributes.len(),
+                    arity
+                );
+            }
+            known_records.insert(name.clone(), table.records.iter().cloned().collect());
+        }
+        let mut derived: HashMap<String, Vec<Vec<T>>> = HashMap::new();
+        for rule in &program.rules() {
+            derived
+                .entry(rule.get_name())
+                .or_default()
+                .extend(self.evaluate_rule(rule, None));
+        }
+        loop {
+            // Only the records that are really new are stored and become the deltas of the next round
+            let mut deltas: HashMap<String, Table<T>> = HashMap::new();
+            for (name, records) in derived {
+                let table = self.tables.get_mut(&name).unwrap();
+                let mut delta = Table::new(name.clone());
+                delta.attributes = table.attributes.clone();
+                for record in records {
+                    if known_records.get_mut(&name).unwrap().insert(record.clone()) {
+                        table.records.push(record.clone());
+                        delta.records.push(record);
+                    }
+                }
+                if !delta.records.is_empty() {
+                    deltas.insert(name, delta);
+                }
+            }
+            if DEBUG {
+                for delta in deltas.values() {
+                    println!("Delta of {}: {} records", delta.name, delta.records.len());
+                }
+            }
+            if deltas.is_empty() {
+                break;
+            }
+            derived = HashMap::new();
+            for rule in &program.rules() {
+                for atom in &rule.atoms() {
+                    if let Some(delta) = deltas.get(&atom.get_relation_name()) {
+                        derived
+                            .entry(rule.get_name())
+                            .or_default()
+                            .extend(self.evaluate_rule(rule, Some((atom, delta))));
+                    }
+                }
+            }
+        }
+    }
+
+    // Evaluate the body of a rule as a sequence of natural joins, binding the given atom to the delta table instead of its relation
+    fn evaluate_rule(
+        &self,
+        rule: &ConjunctiveQuery,
+        delta: Option<(&Atom, &Table<T>)>,
+    ) -> Vec<Vec<T>> {
+        let mut atoms = rule.atoms();
+        let mut atom_tables = HashMap::new();
+        for atom in &atoms {
+            let table = match delta {
+                Some((delta_atom, delta_table)) if delta_atom == atom => delta_table,
+                _ => self
+                    .tables
+                    .get(&atom.get_relation_name())
+                    .unwrap_or_else(|| {
+                        panic!("Table {} not in database", atom.get_relation_name())
+                    }),
+            };
+            atom_tables.insert(atom.get_name(), Database::bind_atom(atom, table));
+        }
+        let mut negated_tables = self.bind_atoms(&rule.negated_atoms());
+        Database::project_anonymous_variables(&rule.negated_atoms(), &mut negated_tables);
+        let post_filters = Database::apply_local_comparisons(rule, &mut atom_tables);
+        let negated_post_filters =
+            Database::apply_local_negations(rule, &mut atom_tables, &negated_tables);
+        Database::project_anonymous_variables(&atoms, &mut atom_tables);
+        // The delta atom is joined first, since it is usually the smallest table
+        atoms.sort_by_key(|x| delta.is_none_or(|(delta_atom, _)| delta_atom != x));
+        // The table without attributes and with a single empty record is the neutral element of the natural join
+        let mut result = Table::new(rule.get_name());
+        result.records.push(Vec::new());
+        for atom in &atoms {
+            result = result.natural_join(atom_tables.get(&atom.get_name()).unwrap());
+        }
+        for comparison in &post_filters {
+            result.records = result.select_comparison(comparison);
+        }
+        for atom in &negated_post_filters {
+            result.records = result.anti_join(negated_tables.get(&atom.get_name()).unwrap());
         }
+        result.project(rule.head())
     }
 
     pub fn print_query_results(&self) {
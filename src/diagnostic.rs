@@ -0,0 +1,150 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    // The query can't be evaluated
+    Error,
+    // The query can be evaluated, but it's probably not the intended one
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    // A head variable not occurring in any positive atom
+    UnsafeHeadVariable(String),
+    // An aggregated variable not occurring in any positive atom
+    UnsafeAggregateVariable(String),
+    // A variable of a negated atom not occurring in any positive atom
+    UnsafeNegatedVariable(String),
+    // A variable of a comparison not occurring in any positive atom
+    UnsafeComparisonVariable(String),
+    // A body without positive atoms
+    EmptyBody,
+    // An atom equal to a previous one of the body, with the same relation and terms
+    DuplicateAtom(String),
+    // A relation that is not in the schema
+    UnknownRelation(String),
+    // An atom whose number of terms differs from the arity of its relation in the schema
+    ArityMismatch {
+        relation: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+// Part of the query a diagnostic refers to, atoms and comparisons are identified by their index in the query
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Location {
+    Query,
+    // Position among all the head terms, aggregates included
+    Head(usize),
+    Atom(usize),
+    NegatedAtom(usize),
+    Comparison(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    severity: Severity,
+    kind: DiagnosticKind,
+    location: Location,
+}
+
+impl Diagnostic {
+    pub fn new(kind: DiagnosticKind, location: Location) -> Diagnostic {
+        let severity = match kind {
+            DiagnosticKind::DuplicateAtom(_) => Severity::Warning,
+            _ => Severity::Error,
+        };
+        Diagnostic {
+            severity,
+            kind,
+            location,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind.clone()
+    }
+
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    // Variable the diagnostic is about, if any
+    pub fn variable(&self) -> Option<String> {
+        match &self.kind {
+            DiagnosticKind::UnsafeHeadVariable(variable)
+            | DiagnosticKind::UnsafeAggregateVariable(variable)
+            | DiagnosticKind::UnsafeNegatedVariable(variable)
+            | DiagnosticKind::UnsafeComparisonVariable(variable) => Some(variable.clone()),
+            _ => None,
+        }
+    }
+
+    // Description of what the query should contain, in the same form as the expected part of a parse error
+    pub fn expected(&self) -> String {
+        match &self.kind {
+            DiagnosticKind::UnsafeHeadVariable(_) => {
+                String::from("a head variable occurring in a positive atom")
+            }
+            DiagnosticKind::UnsafeAggregateVariable(_) => {
+                String::from("an aggregate over a variable occurring in a positive atom")
+            }
+            DiagnosticKind::UnsafeNegatedVariable(_)
+            | DiagnosticKind::UnsafeComparisonVariable(_) => {
+                String::from("a variable occurring in a positive atom")
+            }
+            DiagnosticKind::EmptyBody => String::from("a body with at least one positive atom"),
+            DiagnosticKind::DuplicateAtom(_) => String::from("distinct atoms"),
+            DiagnosticKind::UnknownRelation(_) => String::from("a relation of the schema"),
+            DiagnosticKind::ArityMismatch {
+                relation, expected, ..
+            } => format!("an atom {}/{}", relation, expected),
+        }
+    }
+
+    // Description of what the query actually contains
+    pub fn found(&self) -> String {
+        match &self.kind {
+            DiagnosticKind::UnsafeHeadVariable(variable)
+            | DiagnosticKind::UnsafeAggregateVariable(variable)
+            | DiagnosticKind::UnsafeNegatedVariable(variable)
+            | DiagnosticKind::UnsafeComparisonVariable(variable) => {
+                format!("unsafe variable '{}'", variable)
+            }
+            DiagnosticKind::EmptyBody => String::from("no positive atom"),
+            DiagnosticKind::DuplicateAtom(atom) => format!("duplicate atom {}", atom),
+            DiagnosticKind::UnknownRelation(relation) => {
+                format!("unknown relation '{}'", relation)
+            }
+            DiagnosticKind::ArityMismatch {
+                relation, found, ..
+            } => format!("{}/{}", relation, found),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            severity,
+            self.expected(),
+            self.found()
+        )
+    }
+}
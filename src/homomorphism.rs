@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    conjunctive_query::{Atom, ConjunctiveQuery, Term},
+    hyper_graph::HyperGraph,
+};
+
+// Mapping from the variables of a query to the terms of another one, sending every atom to an atom and the head to the head
+#[derive(Clone, Debug, PartialEq)]
+pub struct Homomorphism {
+    mapping: Vec<(String, Term)>,
+}
+
+// Error of the containment checks on a query with negated atoms, comparisons or aggregates, that homomorphisms don't account for
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedQueryError {
+    query: String,
+}
+
+/*
+    Assignments of the variables of an atom of the source query that send it to an atom of the target query,
+    i.e. the records of the table of the atom over the canonical database of the target query.
+*/
+struct Candidates {
+    variables: Vec<String>,
+    records: Vec<Vec<Term>>,
+}
+
+impl Homomorphism {
+    pub fn get(&self, variable: &str) -> Option<Term> {
+        self.mapping
+            .iter()
+            .find(|x| x.0 == variable)
+            .map(|x| x.1.clone())
+    }
+
+    pub fn mapping(&self) -> Vec<(String, Term)> {
+        self.mapping.clone()
+    }
+
+    // Image of the atom, whose variables must all be mapped except the anonymous ones, that are left as they are
+    pub fn apply(&self, atom: &Atom) -> Vec<Term> {
+        atom.get_terms()
+            .iter()
+            .map(|x| match x {
+                Term::Variable(_) if x.is_anonymous() => x.clone(),
+                Term::Variable(variable) => self
+                    .get(variable)
+                    .unwrap_or_else(|| panic!("Variable {} not mapped", variable)),
+                Term::Constant(_) => x.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Display for Homomorphism {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, (variable, term)) in self.mapping.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} -> {}", variable, term)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl UnsupportedQueryError {
+    pub fn new(query: &str) -> UnsupportedQueryError {
+        UnsupportedQueryError {
+            query: String::from(query),
+        }
+    }
+}
+
+impl Display for UnsupportedQueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Containment is only supported for queries without negated atoms, comparisons and aggregates: {}",
+            self.query
+        )
+    }
+}
+
+impl Error for UnsupportedQueryError {}
+
+impl Candidates {
+    // Assignments agreeing with some assignment of the other atom on the shared variables
+    fn semi_join(&self, other: &Candidates) -> Vec<Vec<Term>> {
+        let shared: Vec<(usize, usize)> = (self.variables.iter().enumerate())
+            .filter_map(|(i, x)| other.variables.iter().position(|y| y == x).map(|j| (i, j)))
+            .collect();
+        (self.records.iter())
+            .filter(|record| {
+                (other.records.iter()).any(|x| shared.iter().all(|(i, j)| record[*i] == x[*j]))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/*
+    Search a homomorphism from the source query to the target one, mapping the head of the source onto the head of the target
+    position by position, i.e. a containment mapping witnessing that the target is contained in the source (Chandra-Merlin).
+    Every atom of the source has to be sent to an atom of the target over the same relation, that is a record of the canonical database
+    of the target, where variables are frozen into values.
+    The atoms are assigned one after the other with backtracking. When the source is alpha-acyclic the assignments of every atom are
+    first reduced with semi-joins bottom-up along its join tree, and the atoms are assigned top-down, so that an assignment of every
+    root extends to a whole homomorphism without backtracking.
+*/
+pub fn find_homomorphism(
+    source: &ConjunctiveQuery,
+    target: &ConjunctiveQuery,
+) -> Option<Homomorphism> {
+    let source_head = source.head();
+    let target_head = target.head();
+    if source_head.len() != target_head.len() {
+        return None;
+    }
+    let mut head_mapping: HashMap<String, Term> = HashMap::new();
+    for (variable, image) in source_head.iter().zip(target_head) {
+        let image = Term::Variable(image);
+        if *head_mapping
+            .entry(variable.clone())
+            .or_insert(image.clone())
+            != image
+        {
+            return None;
+        }
+    }
+    let mut candidates = Vec::new();
+    for atom in &source.atoms() {
+        let atom_candidates = atom_candidates(atom, target, &head_mapping);
+        if atom_candidates.records.is_empty() {
+            return None;
+        }
+        candidates.push(atom_candidates);
+    }
+
+    // Atoms in the order they are assigned, parents before children when the source has a join tree
+    let order = match HyperGraph::new(source).join_tree() {
+        Some(join_tree) => {
+            for i in join_tree.order().iter().rev() {
+                if let Some(p) = join_tree.parent(*i) {
+                    candidates[p].records = candidates[p].semi_join(&candidates[*i]);
+                }
+            }
+            join_tree.order()
+        }
+        None => (0..candidates.len()).collect(),
+    };
+    let atoms: Vec<&Candidates> = order.iter().map(|x| &candidates[*x]).collect();
+    let mut mapping = head_mapping;
+    if !backtrack(&atoms, &mut mapping) {
+        return None;
+    }
+
+    let mut result = Homomorphism {
+        mapping: Vec::new(),
+    };
+    let mut variables = source_head;
+    for atom in &source.atoms() {
+        variables.extend(atom.get_variables());
+    }
+    for variable in variables {
+        if result.get(&variable).is_none() {
+            result
+                .mapping
+                .push((variable.clone(), mapping[&variable].clone()));
+        }
+    }
+    Some(result)
+}
+
+fn atom_candidates(
+    atom: &Atom,
+    target: &ConjunctiveQuery,
+    head_mapping: &HashMap<String, Term>,
+) -> Candidates {
+    let mut result = Candidates {
+        variables: atom.get_variables(),
+        records: Vec::new(),
+    };
+    for target_atom in &target.atoms() {
+        if target_atom.get_relation_name() != atom.get_relation_name()
+            || target_atom.get_terms().len() != atom.get_terms().len()
+        {
+            continue;
+        }
+        // Constants are mapped to themselves, and every variable to a single term
+        let terms = atom.get_terms();
+        let images = target_atom.get_terms();
+        let mut assignment: HashMap<&String, &Term> = HashMap::new();
+        let consistent = (terms.iter().zip(images.iter())).all(|(term, image)| match term {
+            Term::Constant(_) => term == image,
+            Term::Variable(variable) => {
+                let fixed = head_mapping.get(variable).is_none_or(|x| x == image);
+                fixed && *assignment.entry(variable).or_insert(image) == image
+            }
+        });
+        if consistent {
+            let record: Vec<Term> = result
+                .variables
+                .iter()
+                .map(|x| assignment[x].clone())
+                .collect();
+            if !result.records.contains(&record) {
+                result.records.push(record);
+            }
+        }
+    }
+    result
+}
+
+fn backtrack(atoms: &[&Candidates], mapping: &mut HashMap<String, Term>) -> bool {
+    let Some((first, others)) = atoms.split_first() else {
+        return true;
+    };
+    for record in &first.records {
+        let consistent = (first.variables.iter().zip(record))
+            .all(|(variable, term)| mapping.get(variable).is_none_or(|x| x == term));
+        if !consistent {
+            continue;
+        }
+        let new_variables: Vec<String> = first
+            .variables
+            .iter()
+            .filter(|x| !mapping.contains_key(*x))
+            .cloned()
+            .collect();
+        for (variable, term) in first.variables.iter().zip(record) {
+            mapping.insert(variable.clone(), term.clone());
+        }
+        if backtrack(others, mapping) {
+            return true;
+        }
+        for variable in &new_variables {
+            mapping.remove(variable);
+        }
+    }
+    false
+}
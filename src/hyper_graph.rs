@@ -1,3 +1,9 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
 use crate::conjunctive_query::ConjunctiveQuery;
 
 #[derive(Clone)]
@@ -11,6 +17,52 @@ pub struct HyperGraph {
     e: Vec<HyperEdge>,
 }
 
+// Hyper-edge removed by the GYO reduction, with the hyper-edge it was contained in (the witness)
+// Hyper-edges are identified by their index in the hypergraph, that is the position of their atom in the query
+#[derive(Clone)]
+pub struct Ear {
+    edge: (usize, HyperEdge),
+    witness: Option<(usize, HyperEdge)>,
+}
+
+// Outcome of the GYO reduction: the ears in order of removal for an alpha-acyclic hypergraph, the irreducible residual hypergraph otherwise
+#[derive(Clone)]
+pub enum GyoResult {
+    Acyclic(Vec<Ear>),
+    Cyclic(HyperGraph),
+}
+
+/*
+    Join tree of an alpha-acyclic hypergraph over the indexes of its hyper-edges (a forest when the hypergraph is disconnected):
+    the vertices a hyper-edge shares with the ones before it in the order are all contained in its parent, so that the hyper-edges
+    containing any vertex form a connected subtree.
+*/
+#[derive(Clone)]
+pub struct JoinTree {
+    // Hyper-edges in order of visit, every parent comes before its children
+    order: Vec<usize>,
+    parent: Vec<Option<usize>>,
+}
+
+// Outcome of an elimination procedure: the vertices in order of elimination when all of them can be eliminated, the irreducible residual hypergraph otherwise
+#[derive(Clone)]
+pub enum EliminationResult {
+    Acyclic(Vec<String>),
+    Cyclic(HyperGraph),
+}
+
+/*
+    Acyclicity of a hypergraph at every level, from the strictest to the weakest: Berge-acyclic implies gamma-acyclic,
+    that implies beta-acyclic, that implies alpha-acyclic.
+*/
+#[derive(Clone)]
+pub struct AcyclicityProfile {
+    berge: EliminationResult,
+    gamma: EliminationResult,
+    beta: EliminationResult,
+    alpha: GyoResult,
+}
+
 impl HyperEdge {
     pub fn new(v: Vec<String>) -> HyperEdge {
         HyperEdge { vertices: v }
@@ -20,6 +72,10 @@ impl HyperEdge {
         self.vertices.contains(element)
     }
 
+    pub fn vertices(&self) -> Vec<String> {
+        self.vertices.clone()
+    }
+
     pub fn print(&self) {
         let mut i = 0;
         print!("[ ");
@@ -36,6 +92,12 @@ impl HyperEdge {
     }
 }
 
+impl Display for HyperEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]", self.vertices.join(", "))
+    }
+}
+
 impl PartialEq for HyperEdge {
     fn eq(&self, other: &Self) -> bool {
         self.vertices == other.vertices
@@ -48,83 +110,328 @@ impl HyperGraph {
             v: Vec::new(),
             e: Vec::new(),
         };
+        // Anonymous variables join with nothing, so they are neither vertices nor part of any hyper-edge
         for var in cq.var() {
             result.v.push(var);
         }
+        // Negated atoms and comparisons are evaluated as filters on the tables of the positive atoms, so they add no hyper-edge
         for atom in cq.atoms() {
             result.e.push(HyperEdge::new(atom.get_variables()));
         }
         result
     }
 
-    pub fn ears(&self) -> Vec<&HyperEdge> {
-        let mut ears_list = Vec::new();
-        for hyperedge in &self.e {
-            let mut not_exclusive_vertices = Vec::new();
-            for element in &hyperedge.vertices {
-                for he in &self.e {
-                    if he == hyperedge {
-                        continue;
-                    }
-                    if he.contains(element) {
-                        not_exclusive_vertices.push(element);
-                    }
+    pub fn edges(&self) -> Vec<HyperEdge> {
+        self.e.clone()
+    }
+
+    // Vertices sorted by name
+    pub fn vertices(&self) -> Vec<String> {
+        let mut result = self.v.clone();
+        result.sort();
+        result
+    }
+
+    /*
+        GYO reduction: repeatedly remove an ear, i.e. a hyper-edge whose vertices shared with the other hyper-edges are all contained
+        in a single one of them, the witness. The hypergraph is alpha-acyclic if and only if all the hyper-edges can be removed.
+        There is no index from vertices to hyper-edges: every ear is searched comparing each remaining hyper-edge with all the others,
+        so the reduction takes a quadratic number of comparisons per ear and a cubic one overall in the number of hyper-edges.
+    */
+    pub fn gyo(&self) -> GyoResult {
+        let mut remaining: Vec<usize> = (0..self.e.len()).collect();
+        let mut ears = Vec::new();
+        while let Some((i, witness)) = self.find_ear(&remaining) {
+            ears.push(Ear {
+                edge: (i, self.e[i].clone()),
+                witness: witness.map(|j| (j, self.e[j].clone())),
+            });
+            remaining.retain(|x| *x != i);
+        }
+        if remaining.is_empty() {
+            return GyoResult::Acyclic(ears);
+        }
+        // Vertices left in a single hyper-edge are removed too, so that the residual hypergraph is irreducible
+        let shared = |vertex: &String| {
+            remaining
+                .iter()
+                .filter(|x| self.e[**x].contains(vertex))
+                .count()
+                > 1
+        };
+        let mut residual = HyperGraph {
+            v: self.v.iter().filter(|x| shared(x)).cloned().collect(),
+            e: Vec::new(),
+        };
+        for i in &remaining {
+            residual.e.push(HyperEdge::new(
+                self.e[*i]
+                    .vertices
+                    .iter()
+                    .filter(|x| shared(x))
+                    .cloned()
+                    .collect(),
+            ));
+        }
+        GyoResult::Cyclic(residual)
+    }
+
+    // First ear among the remaining hyper-edges, together with its witness, that is None when the ear shares no vertex
+    fn find_ear(&self, remaining: &[usize]) -> Option<(usize, Option<usize>)> {
+        for i in remaining {
+            let others: Vec<usize> = remaining.iter().filter(|x| *x != i).cloned().collect();
+            let shared_vertices: Vec<&String> = (self.e[*i].vertices.iter())
+                .filter(|x| others.iter().any(|j| self.e[*j].contains(x)))
+                .collect();
+            if shared_vertices.is_empty() {
+                return Some((*i, None));
+            }
+            if let Some(j) = others
+                .iter()
+                .find(|j| shared_vertices.iter().all(|x| self.e[**j].contains(x)))
+            {
+                return Some((*i, Some(*j)));
+            }
+        }
+        None
+    }
+
+    pub fn is_acyclic(&self) -> bool {
+        self.join_tree().is_some()
+    }
+
+    /*
+        Linear-time acyclicity test (Tarjan-Yannakakis), returning a join tree when the hypergraph is alpha-acyclic.
+        Maximum cardinality search visits next the hyper-edge with the most vertices already marked, marking its other vertices,
+        and the parent of a hyper-edge is the last visited one that marked some of its vertices: the hypergraph is alpha-acyclic
+        if and only if every hyper-edge has all its marked vertices in its parent.
+        Hyper-edges are kept in buckets by number of marked vertices, an entry is stale when the hyper-edge has been visited
+        or has moved to an upper bucket, so that every step costs as much as the size of the hyper-edge.
+    */
+    pub fn join_tree(&self) -> Option<JoinTree> {
+        let n_edges = self.e.len();
+        // Vertices are numbered, and every vertex is mapped to the hyper-edges containing it
+        let mut vertex_indexes: HashMap<&String, usize> = HashMap::new();
+        let mut edges: Vec<Vec<usize>> = Vec::new();
+        let mut incidences: Vec<Vec<usize>> = Vec::new();
+        for (i, edge) in self.e.iter().enumerate() {
+            let mut vertices = Vec::new();
+            for vertex in &edge.vertices {
+                let n_vertices = vertex_indexes.len();
+                let v = *vertex_indexes.entry(vertex).or_insert(n_vertices);
+                if v == incidences.len() {
+                    incidences.push(Vec::new());
                 }
+                incidences[v].push(i);
+                vertices.push(v);
             }
-            if not_exclusive_vertices.len() == 0 {
-                ears_list.push(hyperedge);
-            } else {
-                for he in &self.e {
-                    if hyperedge == he {
-                        continue;
-                    }
-                    let mut contains_all = true;
-                    for item in &not_exclusive_vertices {
-                        if !he.contains(*item) {
-                            contains_all = false;
-                            break;
+            edges.push(vertices);
+        }
+
+        // Position in the order of the hyper-edge that marked each vertex
+        let mut marked_by: Vec<Option<usize>> = vec![None; incidences.len()];
+        let mut n_marked = vec![0; n_edges];
+        let mut visited = vec![false; n_edges];
+        let mut buckets: Vec<Vec<usize>> = vec![(0..n_edges).rev().collect()];
+        let mut max_marked = 0;
+        let mut order = Vec::new();
+        let mut parent = vec![None; n_edges];
+        // Marked vertices of every hyper-edge, grouped by the parent that must contain them
+        let mut to_check: Vec<Vec<usize>> = vec![Vec::new(); n_edges];
+        while order.len() < n_edges {
+            let Some(i) = buckets[max_marked].pop() else {
+                max_marked -= 1;
+                continue;
+            };
+            if visited[i] || n_marked[i] != max_marked {
+                continue;
+            }
+            visited[i] = true;
+            let position = order.len();
+            order.push(i);
+            if let Some(p) = edges[i].iter().filter_map(|v| marked_by[*v]).max() {
+                parent[i] = Some(order[p]);
+                let marked = edges[i].iter().filter(|v| marked_by[**v].is_some());
+                to_check[order[p]].extend(marked);
+            }
+            for v in &edges[i] {
+                if marked_by[*v].is_some() {
+                    continue;
+                }
+                marked_by[*v] = Some(position);
+                for j in &incidences[*v] {
+                    if !visited[*j] {
+                        n_marked[*j] += 1;
+                        if n_marked[*j] == buckets.len() {
+                            buckets.push(Vec::new());
                         }
-                    }
-                    if contains_all {
-                        ears_list.push(hyperedge);
+                        buckets[n_marked[*j]].push(*j);
+                        max_marked = max_marked.max(n_marked[*j]);
                     }
                 }
             }
         }
-        ears_list
+
+        // Every parent is checked once, stamping its vertices with its index
+        let mut stamps = vec![n_edges; incidences.len()];
+        for (p, vertices) in to_check.iter().enumerate() {
+            for v in &edges[p] {
+                stamps[*v] = p;
+            }
+            if vertices.iter().any(|v| stamps[*v] != p) {
+                return None;
+            }
+        }
+        Some(JoinTree { order, parent })
     }
 
-    pub fn gyo(&self) -> bool {
+    /*
+        Order in which a worst-case optimal join binds the variables. Every order meets the AGM bound, but a variable sharing hyper-edges
+        with many bound ones has a more selective intersection: the next vertex is the one adjacent to the most bound vertices,
+        ties broken by the number of hyper-edges containing it, then by name so that the order is deterministic.
+    */
+    pub fn variable_order(&self) -> Vec<String> {
+        let mut vertices = self.v.clone();
+        vertices.sort();
+        let adjacent = |u: &String, v: &String| {
+            (self.e.iter()).any(|x| x.vertices.contains(u) && x.vertices.contains(v))
+        };
+        let mut order: Vec<String> = Vec::new();
+        while let Some(next) = (vertices.iter())
+            .filter(|x| !order.contains(x))
+            .max_by_key(|x| {
+                let bound_neighbours = order.iter().filter(|y| adjacent(x, y)).count();
+                let degree = self.e.iter().filter(|y| y.vertices.contains(x)).count();
+                (bound_neighbours, degree, Reverse(*x))
+            })
+        {
+            order.push(next.clone());
+        }
+        order
+    }
+
+    /*
+        A query is free-connex when its hypergraph is alpha-acyclic and stays so after adding a hyper-edge made of the head variables:
+        then Yannakakis algorithm can enumerate the answers with constant delay after a linear preprocessing.
+    */
+    pub fn is_free_connex(&self, head: &[String]) -> bool {
+        if !self.is_acyclic() {
+            return false;
+        }
+        let mut head_edge = HyperEdge::new(Vec::new());
+        for variable in head {
+            if !head_edge.contains(variable) {
+                head_edge.vertices.push(variable.clone());
+            }
+        }
         let mut h_ = self.clone();
-        loop {
-            let ears_list = h_.ears();
+        h_.e.push(head_edge);
+        h_.is_acyclic()
+    }
 
-            if ears_list.len() == 0 {
-                break;
+    /*
+        Berge-acyclic: the bipartite incidence graph of vertices and hyper-edges is a forest, i.e. no two hyper-edges share more than one vertex
+        and there is no cycle through distinct hyper-edges. A graph is a forest if and only if removing leaves empties it, here a vertex in
+        at most one hyper-edge or a hyper-edge with at most one vertex, so that the residual hypergraph contains a Berge cycle.
+    */
+    pub fn is_berge_acyclic(&self) -> EliminationResult {
+        self.eliminate(
+            |edges, i| edges[i].len() <= 1,
+            |edges, vertex, _| edges.iter().filter(|x| x.contains(vertex)).count() <= 1,
+        )
+    }
+
+    /*
+        Gamma-acyclic: no gamma-cycle, as decided by the reduction of D'Atri and Moscarini, that removes hyper-edges with at most one vertex
+        or equal to another hyper-edge, and vertices in at most one hyper-edge or in exactly the same hyper-edges as another vertex.
+    */
+    pub fn is_gamma_acyclic(&self) -> EliminationResult {
+        let incidence = |edges: &[Vec<String>], vertex: &String| -> Vec<bool> {
+            edges.iter().map(|x| x.contains(vertex)).collect()
+        };
+        self.eliminate(
+            |edges, i| {
+                edges[i].len() <= 1
+                    || (0..edges.len()).any(|j| {
+                        j != i
+                            && edges[j].len() == edges[i].len()
+                            && edges[j].iter().all(|x| edges[i].contains(x))
+                    })
+            },
+            |edges, vertex, vertices| {
+                let vertex_incidence = incidence(edges, vertex);
+                vertex_incidence.iter().filter(|x| **x).count() <= 1
+                    || (vertices.iter())
+                        .any(|x| x != vertex && incidence(edges, x) == vertex_incidence)
+            },
+        )
+    }
+
+    /*
+        Beta-acyclic: every subset of the hyper-edges is alpha-acyclic. This holds if and only if the vertices can all be eliminated as
+        nest points, i.e. vertices whose hyper-edges form a chain under inclusion, removing them from the hyper-edges.
+    */
+    pub fn is_beta_acyclic(&self) -> EliminationResult {
+        self.eliminate(
+            |edges, i| edges[i].is_empty(),
+            |edges, vertex, _| {
+                let chain: Vec<&Vec<String>> =
+                    edges.iter().filter(|x| x.contains(vertex)).collect();
+                chain.iter().all(|x| {
+                    chain
+                        .iter()
+                        .all(|y| x.iter().all(|v| y.contains(v)) || y.iter().all(|v| x.contains(v)))
+                })
+            },
+        )
+    }
+
+    // Remove hyper-edges and vertices while the rules allow it, a vertex is removed from all the hyper-edges containing it
+    fn eliminate(
+        &self,
+        removable_edge: impl Fn(&[Vec<String>], usize) -> bool,
+        removable_vertex: impl Fn(&[Vec<String>], &String, &[String]) -> bool,
+    ) -> EliminationResult {
+        let mut edges: Vec<Vec<String>> = self.e.iter().map(|x| x.vertices.clone()).collect();
+        // Vertices in order of first occurrence, so that the elimination doesn't depend on the order of the vertex set
+        let mut vertices: Vec<String> = Vec::new();
+        for vertex in edges.iter().flatten() {
+            if !vertices.contains(vertex) {
+                vertices.push(vertex.clone());
             }
-            let e = ears_list[0];
-            let mut exclusive_vertices = e.clone();
-            for element in &e.vertices {
-                for he in &h_.e {
-                    if he == e {
-                        continue;
-                    }
-                    if he.contains(element) && exclusive_vertices.contains(element) {
-                        exclusive_vertices.vertices.swap_remove(
-                            exclusive_vertices
-                                .vertices
-                                .iter()
-                                .position(|x| x == element)
-                                .expect("Generic error!"),
-                        );
-                    }
+        }
+        let mut eliminated = Vec::new();
+        loop {
+            if let Some(i) = (0..edges.len()).find(|i| removable_edge(&edges, *i)) {
+                edges.remove(i);
+            } else if let Some(i) =
+                (0..vertices.len()).find(|i| removable_vertex(&edges, &vertices[*i], &vertices))
+            {
+                let vertex = vertices.remove(i);
+                for edge in &mut edges {
+                    edge.retain(|x| *x != vertex);
                 }
+                eliminated.push(vertex);
+            } else {
+                break;
             }
+        }
+        if vertices.is_empty() {
+            return EliminationResult::Acyclic(eliminated);
+        }
+        EliminationResult::Cyclic(HyperGraph {
+            v: vertices,
+            e: edges.into_iter().map(HyperEdge::new).collect(),
+        })
+    }
 
-            h_.e.swap_remove(h_.e.iter().position(|x| x == e).expect("Generic error!"));
+    pub fn acyclicity_profile(&self) -> AcyclicityProfile {
+        AcyclicityProfile {
+            berge: self.is_berge_acyclic(),
+            gamma: self.is_gamma_acyclic(),
+            beta: self.is_beta_acyclic(),
+            alpha: self.gyo(),
         }
-        let result = h_.e.len() == 0;
-        result
     }
 
     pub fn print(&self) {
@@ -143,3 +450,141 @@ impl HyperGraph {
         }
     }
 }
+
+impl Ear {
+    pub fn edge(&self) -> usize {
+        self.edge.0
+    }
+
+    // Index of the witness, None when the ear shares no vertex with the hyper-edges left
+    pub fn witness(&self) -> Option<usize> {
+        self.witness.as_ref().map(|x| x.0)
+    }
+}
+
+impl Display for Ear {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.witness {
+            Some((j, witness)) => {
+                write!(f, "e{} {} in e{} {}", self.edge.0, self.edge.1, j, witness)
+            }
+            None => write!(f, "e{} {} shares no vertex", self.edge.0, self.edge.1),
+        }
+    }
+}
+
+impl GyoResult {
+    pub fn is_acyclic(&self) -> bool {
+        matches!(self, GyoResult::Acyclic(_))
+    }
+
+    pub fn ears(&self) -> Vec<Ear> {
+        match self {
+            GyoResult::Acyclic(ears) => ears.clone(),
+            GyoResult::Cyclic(_) => Vec::new(),
+        }
+    }
+
+    pub fn residual(&self) -> Option<HyperGraph> {
+        match self {
+            GyoResult::Acyclic(_) => None,
+            GyoResult::Cyclic(residual) => Some(residual.clone()),
+        }
+    }
+}
+
+impl Display for GyoResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GyoResult::Acyclic(ears) => {
+                write!(f, "alpha-acyclic, ears removed:")?;
+                for ear in ears {
+                    write!(f, "\n  {}", ear)?;
+                }
+                Ok(())
+            }
+            GyoResult::Cyclic(residual) => {
+                write!(f, "not alpha-acyclic, residual hypergraph:")?;
+                for edge in &residual.e {
+                    write!(f, "\n  {}", edge)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl EliminationResult {
+    pub fn is_acyclic(&self) -> bool {
+        matches!(self, EliminationResult::Acyclic(_))
+    }
+}
+
+impl Display for EliminationResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EliminationResult::Acyclic(vertices) => {
+                write!(f, "acyclic, vertices eliminated: {}", vertices.join(", "))
+            }
+            EliminationResult::Cyclic(residual) => {
+                write!(f, "not acyclic, residual hypergraph:")?;
+                for edge in &residual.e {
+                    write!(f, "\n  {}", edge)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl AcyclicityProfile {
+    pub fn berge(&self) -> EliminationResult {
+        self.berge.clone()
+    }
+
+    pub fn gamma(&self) -> EliminationResult {
+        self.gamma.clone()
+    }
+
+    pub fn beta(&self) -> EliminationResult {
+        self.beta.clone()
+    }
+
+    pub fn alpha(&self) -> GyoResult {
+        self.alpha.clone()
+    }
+}
+
+impl Display for AcyclicityProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let answer = |acyclic: bool| if acyclic { "yes" } else { "no" };
+        writeln!(f, "Berge-acyclic: {}", answer(self.berge.is_acyclic()))?;
+        writeln!(f, "gamma-acyclic: {}", answer(self.gamma.is_acyclic()))?;
+        writeln!(f, "beta-acyclic: {}", answer(self.beta.is_acyclic()))?;
+        write!(f, "alpha-acyclic: {}", answer(self.alpha.is_acyclic()))
+    }
+}
+
+impl JoinTree {
+    pub fn order(&self) -> Vec<usize> {
+        self.order.clone()
+    }
+
+    pub fn parent(&self, edge: usize) -> Option<usize> {
+        self.parent[edge]
+    }
+
+    pub fn children(&self, edge: usize) -> Vec<usize> {
+        (self.order.iter())
+            .filter(|x| self.parent[**x] == Some(edge))
+            .cloned()
+            .collect()
+    }
+
+    pub fn roots(&self) -> Vec<usize> {
+        (self.order.iter())
+            .filter(|x| self.parent[**x].is_none())
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    hyper_graph::{HyperEdge, HyperGraph},
+    lp,
+};
+
+// Largest number of vertices for which the exact width is computed, since the dynamic programming is exponential in it
+const MAX_EXACT_VERTICES: usize = 16;
+
+// Vertices of a decomposition node, covered by the hyper-edges with the given indexes
+#[derive(Clone)]
+pub struct Bag {
+    vertices: Vec<String>,
+    cover: Vec<usize>,
+}
+
+/*
+    Generalized hypertree decomposition of a hypergraph: a tree of bags (a forest when the hypergraph is disconnected) such that
+    every hyper-edge is contained in some bag and the bags containing any vertex form a connected subtree.
+    Its width is the largest number of hyper-edges covering a bag: the generalized hypertree width of the hypergraph is the minimum width
+    of its decompositions, that is 1 exactly when the hypergraph is alpha-acyclic.
+    Bags are numbered so that every parent comes before its children.
+*/
+#[derive(Clone)]
+pub struct Decomposition {
+    bags: Vec<Bag>,
+    parent: Vec<Option<usize>>,
+}
+
+impl Bag {
+    pub fn vertices(&self) -> Vec<String> {
+        self.vertices.clone()
+    }
+
+    pub fn cover(&self) -> Vec<usize> {
+        self.cover.clone()
+    }
+}
+
+impl Display for Bag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cover: Vec<String> = self.cover.iter().map(|x| format!("e{}", x)).collect();
+        write!(
+            f,
+            "{{{}}} covered by {}",
+            self.vertices.join(", "),
+            cover.join(", ")
+        )
+    }
+}
+
+impl Decomposition {
+    // Decomposition made of a single bag without vertices, covered by no hyper-edge
+    pub fn empty_bag() -> Decomposition {
+        Decomposition {
+            bags: vec![Bag {
+                vertices: Vec::new(),
+                cover: Vec::new(),
+            }],
+            parent: vec![None],
+        }
+    }
+
+    pub fn bags(&self) -> Vec<Bag> {
+        self.bags.clone()
+    }
+
+    pub fn parent(&self, bag: usize) -> Option<usize> {
+        self.parent[bag]
+    }
+
+    pub fn children(&self, bag: usize) -> Vec<usize> {
+        (0..self.bags.len())
+            .filter(|x| self.parent[*x] == Some(bag))
+            .collect()
+    }
+
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.bags.len())
+            .filter(|x| self.parent[*x].is_none())
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.bags.iter().map(|x| x.cover.len()).max().unwrap_or(0)
+    }
+
+    /*
+        Fractional hypertree width of the decomposition: the largest fractional edge cover number of a bag, where the hyper-edges
+        get weights instead of being taken or not. It is never larger than the width.
+    */
+    pub fn fractional_width(&self, h: &HyperGraph) -> f64 {
+        let edges = h.edges();
+        self.bags
+            .iter()
+            .map(|x| fractional_edge_cover(&x.vertices, &edges).0)
+            .fold(0.0, f64::max)
+    }
+
+    fn fmt_subtree(&self, f: &mut Formatter<'_>, bag: usize, depth: usize) -> std::fmt::Result {
+        writeln!(f, "{}{}", "  ".repeat(depth), self.bags[bag])?;
+        for child in self.children(bag) {
+            self.fmt_subtree(f, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Decomposition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for root in self.roots() {
+            self.fmt_subtree(f, root, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+    Fractional edge cover of the vertices: weights of the hyper-edges, minimizing their sum, such that the hyper-edges containing
+    each vertex weigh at least 1 altogether. Returns the cover number and the weights.
+*/
+pub fn fractional_edge_cover(vertices: &[String], edges: &[HyperEdge]) -> (f64, Vec<f64>) {
+    weighted_fractional_edge_cover(vertices, edges, &vec![1.0; edges.len()])
+}
+
+/*
+    Fractional edge cover minimizing the sum of the weights of the hyper-edges multiplied by their costs, that must not be negative.
+    It is computed as the dual of the packing program, that gives weights to the vertices so that every hyper-edge contains vertices
+    weighing at most its cost altogether. Returns the cost of the cover and the weights.
+*/
+pub fn weighted_fractional_edge_cover(
+    vertices: &[String],
+    edges: &[HyperEdge],
+    costs: &[f64],
+) -> (f64, Vec<f64>) {
+    let packing: Vec<Vec<f64>> = edges
+        .iter()
+        .map(|e| {
+            (vertices.iter())
+                .map(|v| if e.contains(v) { 1.0 } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    let solution = lp::maximize(&vec![1.0; vertices.len()], &packing, costs)
+        .unwrap_or_else(|| panic!("Vertex not covered by any hyper-edge"));
+    (solution.value(), solution.dual())
+}
+
+// Decomposition of minimum width if the hypergraph is small enough, of the width found by a heuristic otherwise
+pub fn decompose(h: &HyperGraph) -> Decomposition {
+    optimal_decomposition(h).unwrap_or_else(|| greedy_decomposition(h))
+}
+
+/*
+    Decomposition of minimum width, None if the hypergraph has more than MAX_EXACT_VERTICES vertices.
+    Every decomposition can be turned into the one of an elimination order, eliminating first the vertices of the leaves,
+    whose bags are each made of a vertex and its neighbours not eliminated yet, with no larger width.
+    For every set S of vertices, the dynamic programming finds the best order eliminating S first, as the best over the vertices v of S
+    of eliminating S - {v} first and then v, whose neighbours at that point are the vertices reachable from v through S - {v}.
+*/
+pub fn optimal_decomposition(h: &HyperGraph) -> Option<Decomposition> {
+    let (vertices, edges) = index(h);
+    let n = vertices.len();
+    if n > MAX_EXACT_VERTICES {
+        return None;
+    }
+    let edge_masks: Vec<u64> = (edges.iter())
+        .map(|e| e.iter().fold(0, |mask, v| mask | 1 << v))
+        .collect();
+    let mut adjacency = vec![0u64; n];
+    for mask in &edge_masks {
+        for (v, neighbours) in adjacency.iter_mut().enumerate() {
+            if mask >> v & 1 == 1 {
+                *neighbours |= mask & !(1 << v);
+            }
+        }
+    }
+    let mut covers: HashMap<u64, Vec<usize>> = HashMap::new();
+
+    // Subsets are visited in increasing order, so that S - {v} is always solved before S
+    let full: u64 = (1 << n) - 1;
+    let mut best = vec![0; 1 << n];
+    let mut last = vec![0; 1 << n];
+    for s in 1..=full {
+        best[s as usize] = usize::MAX;
+        for v in (0..n).filter(|v| s >> v & 1 == 1) {
+            let before = s & !(1 << v);
+            let bag = 1 << v | reachable_through(&adjacency, before, v);
+            let width =
+                best[before as usize].max(minimum_cover(bag, &edge_masks, &mut covers).len());
+            if width < best[s as usize] {
+                best[s as usize] = width;
+                last[s as usize] = v;
+            }
+        }
+    }
+    let mut order = Vec::new();
+    let mut s = full;
+    while s != 0 {
+        order.push(last[s as usize]);
+        s &= !(1 << last[s as usize]);
+    }
+    order.reverse();
+
+    Some(from_elimination_order(&vertices, &edges, &order, |bag| {
+        let mask = bag.iter().fold(0, |mask, v| mask | 1 << v);
+        minimum_cover(mask, &edge_masks, &mut covers)
+    }))
+}
+
+/*
+    Decomposition given by the min-fill heuristic, that eliminates first the vertex whose neighbours miss the fewest edges to be a clique,
+    with bags covered greedily by the hyper-edges containing the most vertices not covered yet: its width is an upper bound of the
+    generalized hypertree width, computed in polynomial time.
+*/
+pub fn greedy_decomposition(h: &HyperGraph) -> Decomposition {
+    let (vertices, edges) = index(h);
+    let mut neighbours = primal_graph(vertices.len(), &edges);
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    let mut order = Vec::new();
+    while !remaining.is_empty() {
+        let fill = |v: usize| {
+            let mut missing = 0;
+            for a in &neighbours[v] {
+                missing += neighbours[v]
+                    .iter()
+                    .filter(|b| a < b && !neighbours[*a].contains(b))
+                    .count();
+            }
+            (missing, neighbours[v].len())
+        };
+        let i = (0..remaining.len())
+            .min_by_key(|i| fill(remaining[*i]))
+            .unwrap();
+        let v = remaining.remove(i);
+        eliminate(&mut neighbours, v);
+        order.push(v);
+    }
+    from_elimination_order(&vertices, &edges, &order, |bag| {
+        let mut uncovered: HashSet<usize> = bag.iter().cloned().collect();
+        let mut cover = Vec::new();
+        while !uncovered.is_empty() {
+            let i = (0..edges.len())
+                .max_by_key(|i| {
+                    (
+                        edges[*i].iter().filter(|v| uncovered.contains(v)).count(),
+                        usize::MAX - i,
+                    )
+                })
+                .unwrap();
+            uncovered.retain(|v| !edges[i].contains(v));
+            cover.push(i);
+        }
+        cover
+    })
+}
+
+// Vertices in order of first occurrence in the hyper-edges, and hyper-edges as lists of vertex indexes
+fn index(h: &HyperGraph) -> (Vec<String>, Vec<Vec<usize>>) {
+    let mut vertices: Vec<String> = Vec::new();
+    let mut edges = Vec::new();
+    for edge in &h.edges() {
+        let mut indexes = Vec::new();
+        for vertex in edge.vertices() {
+            let i = match vertices.iter().position(|x| *x == vertex) {
+                Some(i) => i,
+                None => {
+                    vertices.push(vertex);
+                    vertices.len() - 1
+                }
+            };
+            indexes.push(i);
+        }
+        edges.push(indexes);
+    }
+    (vertices, edges)
+}
+
+// Graph connecting the vertices that belong to a same hyper-edge
+fn primal_graph(n: usize, edges: &[Vec<usize>]) -> Vec<HashSet<usize>> {
+    let mut result = vec![HashSet::new(); n];
+    for edge in edges {
+        for a in edge {
+            for b in edge {
+                if a != b {
+                    result[*a].insert(*b);
+                }
+            }
+        }
+    }
+    result
+}
+
+// Remove the vertex from the graph, turning its neighbours into a clique
+fn eliminate(neighbours: &mut [HashSet<usize>], v: usize) {
+    let later: Vec<usize> = neighbours[v].drain().collect();
+    for a in &later {
+        neighbours[*a].remove(&v);
+        for b in &later {
+            if a != b {
+                neighbours[*a].insert(*b);
+            }
+        }
+    }
+}
+
+// Vertices outside the set and different from v that can be reached from v going through vertices of the set only
+fn reachable_through(adjacency: &[u64], set: u64, v: usize) -> u64 {
+    let mut reached: u64 = 1 << v;
+    let mut frontier: u64 = 1 << v;
+    while frontier != 0 {
+        let mut next = 0;
+        for (u, neighbours) in adjacency.iter().enumerate() {
+            if frontier >> u & 1 == 1 {
+                next |= neighbours;
+            }
+        }
+        next &= !reached;
+        reached |= next;
+        frontier = next & set;
+    }
+    reached & !set & !(1 << v)
+}
+
+// Fewest hyper-edges covering the vertices of the mask, branching on the hyper-edges containing its lowest vertex
+fn minimum_cover(
+    mask: u64,
+    edge_masks: &[u64],
+    covers: &mut HashMap<u64, Vec<usize>>,
+) -> Vec<usize> {
+    if mask == 0 {
+        return Vec::new();
+    }
+    if let Some(cover) = covers.get(&mask) {
+        return cover.clone();
+    }
+    let v = mask.trailing_zeros();
+    let mut result: Option<Vec<usize>> = None;
+    for (i, edge_mask) in edge_masks.iter().enumerate() {
+        if edge_mask >> v & 1 == 1 {
+            let mut cover = minimum_cover(mask & !edge_mask, edge_masks, covers);
+            if result.as_ref().is_none_or(|x| cover.len() + 1 < x.len()) {
+                cover.push(i);
+                result = Some(cover);
+            }
+        }
+    }
+    let result = result.expect("Vertex not covered by any hyper-edge");
+    covers.insert(mask, result.clone());
+    result
+}
+
+/*
+    Decomposition of an elimination order: the bag of a vertex is made of the vertex and its neighbours when it is eliminated,
+    and its parent is the bag of the first of those neighbours to be eliminated next.
+    A bag contained in its parent, or containing it, is merged into it, and the bags left are covered with the given function.
+*/
+fn from_elimination_order(
+    vertices: &[String],
+    edges: &[Vec<usize>],
+    order: &[usize],
+    mut cover: impl FnMut(&[usize]) -> Vec<usize>,
+) -> Decomposition {
+    let mut position = vec![0; vertices.len()];
+    for (i, v) in order.iter().enumerate() {
+        position[*v] = i;
+    }
+    let mut neighbours = primal_graph(vertices.len(), edges);
+    let mut bags: Vec<Option<HashSet<usize>>> = Vec::new();
+    let mut parent: Vec<Option<usize>> = Vec::new();
+    for v in order {
+        let mut bag: HashSet<usize> = neighbours[*v].clone();
+        parent.push(bag.iter().map(|x| position[*x]).min());
+        bag.insert(*v);
+        bags.push(Some(bag));
+        eliminate(&mut neighbours, *v);
+    }
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        for c in 0..bags.len() {
+            let Some(p) = parent[c] else {
+                continue;
+            };
+            let (Some(child), Some(parent_bag)) = (&bags[c], &bags[p]) else {
+                continue;
+            };
+            if parent_bag.is_subset(child) {
+                bags[p] = bags[c].take();
+            } else if child.is_subset(parent_bag) {
+                bags[c] = None;
+            } else {
+                continue;
+            }
+            for x in parent.iter_mut() {
+                if *x == Some(c) {
+                    *x = Some(p);
+                }
+            }
+            merged = true;
+        }
+    }
+
+    // Parents come after their children in the elimination order, so the bags left are renumbered backwards
+    let kept: Vec<usize> = (0..bags.len())
+        .rev()
+        .filter(|x| bags[*x].is_some())
+        .collect();
+    let mut result = Decomposition {
+        bags: Vec::new(),
+        parent: Vec::new(),
+    };
+    for i in &kept {
+        let mut bag: Vec<usize> = bags[*i].as_ref().unwrap().iter().cloned().collect();
+        bag.sort();
+        result.bags.push(Bag {
+            vertices: bag.iter().map(|x| vertices[*x].clone()).collect(),
+            cover: cover(&bag),
+        });
+        result
+            .parent
+            .push(parent[*i].map(|p| kept.iter().position(|x| *x == p).unwrap()));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conjunctive_query::ConjunctiveQuery;
+
+    const TRIANGLE: &str = "q(x, y, z) :- R(x, y), S(y, z), T(z, x)";
+    const PATH: &str = "q(x, w) :- R(x, y), S(y, z), T(z, w)";
+
+    fn hypergraph(query: &str) -> HyperGraph {
+        HyperGraph::new(&ConjunctiveQuery::new(query))
+    }
+
+    fn assert_covers_every_edge(decomposition: &Decomposition, h: &HyperGraph) {
+        for edge in h.edges() {
+            assert!(decomposition
+                .bags()
+                .iter()
+                .any(|x| edge.vertices().iter().all(|v| x.vertices().contains(v))));
+        }
+    }
+
+    #[test]
+    fn triangle_fractional_edge_cover() {
+        let h = hypergraph(TRIANGLE);
+        let vertices = vec![String::from("x"), String::from("y"), String::from("z")];
+        let (cover_number, weights) = fractional_edge_cover(&vertices, &h.edges());
+        assert!((cover_number - 1.5).abs() < 1e-9);
+        for weight in weights {
+            assert!((weight - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn triangle_optimal_decomposition() {
+        let h = hypergraph(TRIANGLE);
+        let decomposition = optimal_decomposition(&h).unwrap();
+        assert_covers_every_edge(&decomposition, &h);
+        assert_eq!(decomposition.width(), 2);
+        assert!((decomposition.fractional_width(&h) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn triangle_greedy_decomposition() {
+        let h = hypergraph(TRIANGLE);
+        let decomposition = greedy_decomposition(&h);
+        assert_covers_every_edge(&decomposition, &h);
+        assert_eq!(decomposition.width(), 2);
+        assert!((decomposition.fractional_width(&h) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn acyclic_query_has_width_one() {
+        let h = hypergraph(PATH);
+        let optimal = optimal_decomposition(&h).unwrap();
+        assert_covers_every_edge(&optimal, &h);
+        assert_eq!(optimal.width(), 1);
+        assert!((optimal.fractional_width(&h) - 1.0).abs() < 1e-9);
+        let greedy = greedy_decomposition(&h);
+        assert_covers_every_edge(&greedy, &h);
+        assert_eq!(greedy.width(), 1);
+    }
+}
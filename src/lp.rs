@@ -0,0 +1,142 @@
+// Tolerance on the comparisons between floating point values of the tableau
+const EPSILON: f64 = 1e-9;
+
+// Optimal solution of a linear program, together with the optimal solution of its dual
+#[derive(Clone, Debug)]
+pub struct Solution {
+    value: f64,
+    primal: Vec<f64>,
+    dual: Vec<f64>,
+}
+
+impl Solution {
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn primal(&self) -> Vec<f64> {
+        self.primal.clone()
+    }
+
+    pub fn dual(&self) -> Vec<f64> {
+        self.dual.clone()
+    }
+}
+
+/*
+    Solve the linear program
+        maximize c x  subject to  A x <= b, x >= 0
+    where b >= 0, so that the origin is a feasible basic solution and no first phase is needed, with the simplex method on a dense tableau.
+    The dual program is
+        minimize b y  subject to  A^T y >= c, y >= 0
+    and its optimal solution is read from the reduced costs of the slack variables.
+    Bland's rule (smallest index entering and leaving the basis) prevents cycling on degenerate programs.
+    Returns None if the program is unbounded, and panics if b has negative entries.
+*/
+pub fn maximize(c: &[f64], a: &[Vec<f64>], b: &[f64]) -> Option<Solution> {
+    let n = c.len();
+    let m = b.len();
+    if b.iter().any(|x| *x < 0.0) {
+        panic!("The origin must be feasible, but b has negative entries");
+    }
+    // Row i holds constraint i over the n variables and the m slack variables, followed by its right-hand side
+    let mut tableau: Vec<Vec<f64>> = Vec::new();
+    for i in 0..m {
+        let mut row = a[i].clone();
+        row.resize(n + m + 1, 0.0);
+        row[n + i] = 1.0;
+        row[n + m] = b[i];
+        tableau.push(row);
+    }
+    // Reduced costs, whose last entry is minus the value of the current solution
+    let mut objective = c.to_vec();
+    objective.resize(n + m + 1, 0.0);
+    let mut basis: Vec<usize> = (n..n + m).collect();
+
+    while let Some(entering) = (0..n + m).find(|j| objective[*j] > EPSILON) {
+        let mut leaving: Option<usize> = None;
+        for i in 0..m {
+            if tableau[i][entering] <= EPSILON {
+                continue;
+            }
+            let ratio = tableau[i][n + m] / tableau[i][entering];
+            leaving = match leaving {
+                Some(l) => {
+                    let best = tableau[l][n + m] / tableau[l][entering];
+                    if ratio < best - EPSILON || (ratio < best + EPSILON && basis[i] < basis[l]) {
+                        Some(i)
+                    } else {
+                        Some(l)
+                    }
+                }
+                None => Some(i),
+            };
+        }
+        let leaving = leaving?;
+
+        let pivot = tableau[leaving][entering];
+        for x in tableau[leaving].iter_mut() {
+            *x /= pivot;
+        }
+        let pivot_row = tableau[leaving].clone();
+        for (i, row) in tableau.iter_mut().enumerate() {
+            let factor = row[entering];
+            if i != leaving && factor != 0.0 {
+                for (x, y) in row.iter_mut().zip(&pivot_row) {
+                    *x -= factor * y;
+                }
+            }
+        }
+        let factor = objective[entering];
+        for (x, y) in objective.iter_mut().zip(&pivot_row) {
+            *x -= factor * y;
+        }
+        basis[leaving] = entering;
+    }
+
+    let mut primal = vec![0.0; n];
+    for (i, variable) in basis.iter().enumerate() {
+        if *variable < n {
+            primal[*variable] = tableau[i][n + m];
+        }
+    }
+    Some(Solution {
+        value: -objective[n + m],
+        primal,
+        dual: (0..m).map(|i| -objective[n + i]).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len());
+        for (x, y) in found.iter().zip(expected) {
+            assert!((x - y).abs() < 1e-9, "{:?} != {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn optimal_primal_and_dual() {
+        // maximize 3x + 2y  subject to  x + y <= 4, x + 3y <= 6, x <= 3
+        let a = vec![vec![1.0, 1.0], vec![1.0, 3.0], vec![1.0, 0.0]];
+        let solution = maximize(&[3.0, 2.0], &a, &[4.0, 6.0, 3.0]).unwrap();
+        assert!((solution.value() - 11.0).abs() < 1e-9);
+        assert_close(&solution.primal(), &[3.0, 1.0]);
+        assert_close(&solution.dual(), &[2.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn unbounded() {
+        // maximize x  subject to  -x + y <= 1
+        assert!(maximize(&[1.0, 0.0], &[vec![-1.0, 1.0]], &[1.0]).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "b has negative entries")]
+    fn infeasible_origin() {
+        maximize(&[1.0], &[vec![1.0]], &[-1.0]);
+    }
+}
@@ -1,7 +1,18 @@
+pub mod agm;
 pub mod conjunctive_query;
 pub mod database;
+pub mod diagnostic;
+pub mod homomorphism;
 pub mod hyper_graph;
-pub mod join_forest;
+pub mod hypertree;
+pub mod lp;
+pub mod parse_error;
+pub mod parser;
+pub mod program;
+pub mod schema;
+pub mod sql;
+pub mod trie;
+pub mod union_query;
 
 use conjunctive_query::ConjunctiveQuery;
 use hyper_graph::HyperGraph;
@@ -21,26 +32,51 @@ fn main() {
     cq.print();
     let h = HyperGraph::new(&cq);
     h.print();
-    println!("Hypergraph is alpha-acyclic: {}", h.gyo());
-    if h.gyo() {
-        if !Path::new(DATABASE_FILE).exists() {
-            let mut database_file =
-                File::create(DATABASE_FILE).expect("Error creating the database file!");
-            println!("Generating random data...");
-            cq.generate_random_data(&mut database_file);
-        }
+    let gyo = h.gyo();
+    println!("Hypergraph is {}", gyo);
+    println!("Query class: {}", cq.classify());
+    println!("{}", cq.acyclicity_profile());
+    // Cyclic queries are answered over the bags of a hypertree decomposition, that form an acyclic query
+    let decomposition = if gyo.is_acyclic() {
+        None
+    } else {
+        let decomposition = hypertree::decompose(&h);
+        println!("Hypertree decomposition:");
+        print!("{}", decomposition);
+        println!("Generalized hypertree width: {}", decomposition.width());
+        println!(
+            "Fractional hypertree width of the decomposition: {:.3}",
+            decomposition.fractional_width(&h)
+        );
+        Some(decomposition)
+    };
+    if !Path::new(DATABASE_FILE).exists() {
         let mut database_file =
-            File::open(DATABASE_FILE).expect("Error opening the database file!");
-        println!("Loading generated data...");
-        let mut database: Database<u64> = Database::new(&mut database_file);
-        println!("Start timing...");
+            File::create(DATABASE_FILE).expect("Error creating the database file!");
+        println!("Generating random data...");
+        cq.generate_random_data(&mut database_file);
+    }
+    let mut database_file =
+        File::open(DATABASE_FILE).expect("Error opening the database file!");
+    println!("Loading generated data...");
+    let mut database: Database<u64> = Database::new(&mut database_file);
+    print!("{}", database.agm_bound(&cq));
+    println!("Start timing...");
+    let now = Instant::now();
+    match &decomposition {
+        None => database.yannakakis(&cq),
+        Some(decomposition) => database.yannakakis_decomposition(&cq, decomposition),
+    }
+    let elapsed_time = now.elapsed();
+    println!(
+        "Yannakakis algorithm ran in {:.3}s",
+        elapsed_time.as_secs_f64()
+    );
+    if decomposition.is_some() {
         let now = Instant::now();
-        database.yannakakis(&cq);
+        database.generic_join(&cq);
         let elapsed_time = now.elapsed();
-        println!(
-            "Yannakakis algorithm ran in {:.3}s",
-            elapsed_time.as_secs_f64()
-        );
-        database.print_query_results();
+        println!("Generic join ran in {:.3}s", elapsed_time.as_secs_f64());
     }
+    database.print_query_results();
 }
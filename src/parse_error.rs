@@ -0,0 +1,95 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    input: String,
+    offset: usize,
+    expected: String,
+    found: String,
+}
+
+impl ParseError {
+    pub fn new(input: &str, offset: usize, expected: &str) -> ParseError {
+        // Describe what is actually at the error position, so that the message is meaningful even without the caret
+        let found = match input[offset..].chars().next() {
+            Some(c) => format!("'{}'", c),
+            None => String::from("end of input"),
+        };
+        ParseError {
+            input: String::from(input),
+            offset,
+            expected: String::from(expected),
+            found,
+        }
+    }
+
+    pub fn unexpected(input: &str, offset: usize, expected: &str, found: &str) -> ParseError {
+        ParseError {
+            input: String::from(input),
+            offset,
+            expected: String::from(expected),
+            found: String::from(found),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn expected(&self) -> String {
+        self.expected.clone()
+    }
+
+    pub fn found(&self) -> String {
+        self.found.clone()
+    }
+
+    // Returns the line and the column (both starting from 1) of the error offset
+    pub fn line_column(&self) -> (usize, usize) {
+        let before = &self.input[..self.offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /*
+        Render the line of the input containing the error with a caret under the wrong position, e.g.:
+            q(x) :- R(x y)
+                        ^ expected ',' or ')', found 'y'
+    */
+    pub fn render(&self) -> String {
+        let line_start = self.input[..self.offset]
+            .rfind('\n')
+            .map(|x| x + 1)
+            .unwrap_or(0);
+        let line_end = self.input[self.offset..]
+            .find('\n')
+            .map(|x| x + self.offset)
+            .unwrap_or(self.input.len());
+        let padding: String = self.input[line_start..self.offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "{}\n{}^ expected {}, found {}",
+            &self.input[line_start..line_end],
+            padding,
+            self.expected,
+            self.found
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (line, column) = self.line_column();
+        writeln!(
+            f,
+            "Error! The input is not a conjunctive query (line {}, column {}):",
+            line, column
+        )?;
+        write!(f, "{}", self.render())
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,699 @@
+use crate::{
+    conjunctive_query::{AggregateFunction, ComparisonOperator, Constant, Term},
+    parse_error::ParseError,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Identifier(String),
+    Number(String),
+    Text(String),
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+    Period,
+    Star,
+    Implication,
+    Comparison(ComparisonOperator),
+    End,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+// Abstract syntax tree of a rule, every node keeps the byte offset it starts at in the input
+#[derive(Clone, Debug)]
+pub struct TermNode {
+    pub term: Term,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct AtomNode {
+    pub name: String,
+    pub terms: Vec<TermNode>,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct ComparisonNode {
+    pub left: TermNode,
+    pub operator: ComparisonOperator,
+    pub right: TermNode,
+}
+
+// Aggregate in the head, e.g. sum(z), the position is the one among all the head terms
+#[derive(Clone, Debug)]
+pub struct AggregateNode {
+    pub function: AggregateFunction,
+    pub variable: Option<String>,
+    pub position: usize,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct RuleNode {
+    pub head: AtomNode,
+    pub head_aggregates: Vec<AggregateNode>,
+    pub body: Vec<AtomNode>,
+    pub negated_body: Vec<AtomNode>,
+    pub comparisons: Vec<ComparisonNode>,
+}
+
+// Abstract syntax tree of a select-project-join SQL query
+#[derive(Clone, Debug)]
+pub struct ColumnNode {
+    // Name or alias of the table given in the FROM clause, if the column is qualified
+    pub table: Option<String>,
+    pub name: String,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum OperandNode {
+    Column(ColumnNode),
+    Constant(TermNode),
+}
+
+#[derive(Clone, Debug)]
+pub struct ConditionNode {
+    pub left: OperandNode,
+    pub operator: ComparisonOperator,
+    pub right: OperandNode,
+}
+
+#[derive(Clone, Debug)]
+pub struct TableNode {
+    pub name: String,
+    pub alias: Option<String>,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectNode {
+    // SELECT * is represented by an empty list of columns
+    pub columns: Vec<ColumnNode>,
+    pub offset: usize,
+    pub tables: Vec<TableNode>,
+    pub conditions: Vec<ConditionNode>,
+}
+
+// Keywords of SQL, that can't be used as table aliases
+const SQL_KEYWORDS: [&str; 6] = ["select", "distinct", "from", "as", "where", "and"];
+
+pub struct Parser {
+    input: String,
+    tokens: Vec<Token>,
+    position: usize,
+    // Occurrences of the anonymous variable parsed so far, used to give each of them its own name
+    anonymous_variables: usize,
+}
+
+impl TokenKind {
+    pub fn describe(&self) -> String {
+        match self {
+            TokenKind::Identifier(name) => format!("'{}'", name),
+            TokenKind::Number(value) => format!("'{}'", value),
+            TokenKind::Text(value) => format!("'\"{}\"'", value),
+            TokenKind::LeftParenthesis => String::from("'('"),
+            TokenKind::RightParenthesis => String::from("')'"),
+            TokenKind::Comma => String::from("','"),
+            TokenKind::Period => String::from("'.'"),
+            TokenKind::Star => String::from("'*'"),
+            TokenKind::Implication => String::from("':-'"),
+            TokenKind::Comparison(operator) => format!("'{}'", operator),
+            TokenKind::End => String::from("end of input"),
+        }
+    }
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut result = Vec::new();
+    let bytes = input.as_bytes();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let c = bytes[offset];
+        if c.is_ascii_whitespace() {
+            offset += 1;
+        } else if c == b'%'
+            || input[offset..].starts_with("//")
+            || input[offset..].starts_with("--")
+        {
+            // Comments go on until the end of the line
+            offset = input[offset..]
+                .find('\n')
+                .map(|x| x + offset)
+                .unwrap_or(input.len());
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = offset;
+            while offset < bytes.len()
+                && (bytes[offset].is_ascii_alphanumeric() || bytes[offset] == b'_')
+            {
+                offset += 1;
+            }
+            result.push(Token {
+                kind: TokenKind::Identifier(String::from(&input[start..offset])),
+                offset: start,
+            });
+        } else if c.is_ascii_digit()
+            || (c == b'-' && offset + 1 < bytes.len() && bytes[offset + 1].is_ascii_digit())
+        {
+            // Numbers are an optional minus sign, some digits and an optional decimal part
+            let start = offset;
+            offset += 1;
+            while offset < bytes.len() && bytes[offset].is_ascii_digit() {
+                offset += 1;
+            }
+            if offset + 1 < bytes.len()
+                && bytes[offset] == b'.'
+                && bytes[offset + 1].is_ascii_digit()
+            {
+                offset += 1;
+                while offset < bytes.len() && bytes[offset].is_ascii_digit() {
+                    offset += 1;
+                }
+            }
+            result.push(Token {
+                kind: TokenKind::Number(String::from(&input[start..offset])),
+                offset: start,
+            });
+        } else if c == b'"' {
+            // Strings are delimited by double quotes, inside them \" and \\ are the only escape sequences
+            let start = offset;
+            let mut value = String::new();
+            let mut characters = input[offset + 1..].char_indices();
+            loop {
+                match characters.next() {
+                    Some((i, '"')) => {
+                        offset += i + 2;
+                        break;
+                    }
+                    Some((_, '\\')) => match characters.next() {
+                        Some((_, escaped)) if escaped == '"' || escaped == '\\' => {
+                            value.push(escaped)
+                        }
+                        Some((i, _)) => {
+                            return Err(ParseError::new(input, start + i + 1, "'\"' or '\\'"))
+                        }
+                        None => return Err(ParseError::new(input, input.len(), "'\"'")),
+                    },
+                    Some((_, other)) => value.push(other),
+                    None => return Err(ParseError::new(input, input.len(), "'\"'")),
+                }
+            }
+            result.push(Token {
+                kind: TokenKind::Text(value),
+                offset: start,
+            });
+        } else if c == b'\'' {
+            // Strings can also be delimited by single quotes as in SQL, where a quote is escaped by doubling it
+            let start = offset;
+            let mut value = String::new();
+            let mut characters = input[offset + 1..].char_indices().peekable();
+            loop {
+                match characters.next() {
+                    Some((_, '\'')) if characters.peek().map(|x| x.1) == Some('\'') => {
+                        characters.next();
+                        value.push('\'');
+                    }
+                    Some((i, '\'')) => {
+                        offset += i + 2;
+                        break;
+                    }
+                    Some((_, other)) => value.push(other),
+                    None => return Err(ParseError::new(input, input.len(), "\"'\"")),
+                }
+            }
+            result.push(Token {
+                kind: TokenKind::Text(value),
+                offset: start,
+            });
+        } else if c == b'(' {
+            result.push(Token {
+                kind: TokenKind::LeftParenthesis,
+                offset,
+            });
+            offset += 1;
+        } else if c == b')' {
+            result.push(Token {
+                kind: TokenKind::RightParenthesis,
+                offset,
+            });
+            offset += 1;
+        } else if c == b',' {
+            result.push(Token {
+                kind: TokenKind::Comma,
+                offset,
+            });
+            offset += 1;
+        } else if c == b'*' {
+            result.push(Token {
+                kind: TokenKind::Star,
+                offset,
+            });
+            offset += 1;
+        } else if c == b'.' {
+            result.push(Token {
+                kind: TokenKind::Period,
+                offset,
+            });
+            offset += 1;
+        } else if input[offset..].starts_with(":-") {
+            result.push(Token {
+                kind: TokenKind::Implication,
+                offset,
+            });
+            offset += 2;
+        } else if let Some((operator, length)) = ComparisonOperator::lex(&input[offset..]) {
+            result.push(Token {
+                kind: TokenKind::Comparison(operator),
+                offset,
+            });
+            offset += length;
+        } else {
+            return Err(ParseError::new(
+                input,
+                offset,
+                "a name, a constant, '(', ')', ',', '.', '*', ':-' or a comparison operator",
+            ));
+        }
+    }
+    result.push(Token {
+        kind: TokenKind::End,
+        offset: input.len(),
+    });
+    Ok(result)
+}
+
+impl Parser {
+    pub fn new(input: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            input: String::from(input),
+            tokens: tokenize(input)?,
+            position: 0,
+            anonymous_variables: 0,
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &str) -> ParseError {
+        let token = self.peek();
+        ParseError::unexpected(&self.input, token.offset, expected, &token.kind.describe())
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+        if self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.error(&kind.describe()))
+        }
+    }
+
+    fn parse_identifier(&mut self, expected: &str) -> Result<(String, usize), ParseError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Identifier(name) => {
+                self.advance();
+                Ok((name, token.offset))
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    // Variable of the head or of an aggregate, where the anonymous variable is meaningless
+    fn parse_variable(&mut self) -> Result<(String, usize), ParseError> {
+        let (name, offset) = self.parse_identifier("a variable name")?;
+        if name == "_" {
+            return Err(ParseError::unexpected(
+                &self.input,
+                offset,
+                "a named variable",
+                "anonymous variable '_'",
+            ));
+        }
+        Ok((name, offset))
+    }
+
+    // term := variable | "_" | number | string
+    fn parse_term(&mut self) -> Result<TermNode, ParseError> {
+        let token = self.peek().clone();
+        let term = match token.kind {
+            TokenKind::Identifier(name) if name == "_" => {
+                self.anonymous_variables += 1;
+                Term::Variable(format!("_#{}", self.anonymous_variables))
+            }
+            TokenKind::Identifier(name) => Term::Variable(name),
+            TokenKind::Number(value) => Term::Constant(Constant::Number(value)),
+            TokenKind::Text(value) => Term::Constant(Constant::Text(value)),
+            _ => return Err(self.error("a variable name or a constant")),
+        };
+        self.advance();
+        Ok(TermNode {
+            term,
+            offset: token.offset,
+        })
+    }
+
+    // aggregate := function "(" ( "*" | variable ) ")", where only count accepts "*"
+    fn parse_aggregate(&mut self, position: usize) -> Result<AggregateNode, ParseError> {
+        let (name, offset) = self.parse_identifier("an aggregate function")?;
+        let function = match AggregateFunction::from_name(&name) {
+            Some(function) => function,
+            None => {
+                return Err(ParseError::unexpected(
+                    &self.input,
+                    offset,
+                    "an aggregate function (count, sum, min or max)",
+                    format!("'{}'", name).as_str(),
+                ))
+            }
+        };
+        self.expect(TokenKind::LeftParenthesis)?;
+        let variable =
+            if function == AggregateFunction::Count && self.peek().kind == TokenKind::Star {
+                self.advance();
+                None
+            } else {
+                Some(self.parse_variable()?.0)
+            };
+        self.expect(TokenKind::RightParenthesis)?;
+        Ok(AggregateNode {
+            function,
+            variable,
+            position,
+            offset,
+        })
+    }
+
+    // head := name "(" [ head_term ( "," head_term )* ] ")", where a head term is either a variable or an aggregate
+    fn parse_head(&mut self) -> Result<(AtomNode, Vec<AggregateNode>), ParseError> {
+        let (name, offset) = self.parse_identifier("a query name")?;
+        self.expect(TokenKind::LeftParenthesis)?;
+        let mut head = AtomNode {
+            name,
+            terms: Vec::new(),
+            offset,
+        };
+        let mut aggregates = Vec::new();
+        if self.peek().kind == TokenKind::RightParenthesis {
+            self.advance();
+            return Ok((head, aggregates));
+        }
+        loop {
+            let position = head.terms.len() + aggregates.len();
+            let is_aggregate = matches!(self.peek().kind, TokenKind::Identifier(_))
+                && self.tokens[self.position + 1].kind == TokenKind::LeftParenthesis;
+            if is_aggregate {
+                aggregates.push(self.parse_aggregate(position)?);
+            } else {
+                let (variable, variable_offset) = self.parse_variable()?;
+                head.terms.push(TermNode {
+                    term: Term::Variable(variable),
+                    offset: variable_offset,
+                });
+            }
+            match self.peek().kind {
+                TokenKind::Comma => {
+                    self.advance();
+                }
+                TokenKind::RightParenthesis => {
+                    self.advance();
+                    return Ok((head, aggregates));
+                }
+                _ => return Err(self.error("',' or ')'")),
+            }
+        }
+    }
+
+    // atom := name "(" term ( "," term )* ")"
+    fn parse_atom(&mut self) -> Result<AtomNode, ParseError> {
+        let (name, offset) = self.parse_identifier("an atom name")?;
+        self.expect(TokenKind::LeftParenthesis)?;
+        let mut terms = Vec::new();
+        loop {
+            terms.push(self.parse_term()?);
+            match self.peek().kind {
+                TokenKind::Comma => {
+                    self.advance();
+                }
+                TokenKind::RightParenthesis => {
+                    self.advance();
+                    break;
+                }
+                _ => return Err(self.error("',' or ')'")),
+            }
+        }
+        Ok(AtomNode {
+            name,
+            terms,
+            offset,
+        })
+    }
+
+    // comparison := term operator term, where neither term is the anonymous variable
+    fn parse_comparison(&mut self) -> Result<ComparisonNode, ParseError> {
+        let left = self.parse_term()?;
+        let operator = match self.peek().kind {
+            TokenKind::Comparison(operator) => operator,
+            _ => return Err(self.error("a comparison operator")),
+        };
+        self.advance();
+        let right = self.parse_term()?;
+        for term in [&left, &right] {
+            if term.term.is_anonymous() {
+                return Err(ParseError::unexpected(
+                    &self.input,
+                    term.offset,
+                    "a named variable or a constant",
+                    "anonymous variable '_'",
+                ));
+            }
+        }
+        Ok(ComparisonNode {
+            left,
+            operator,
+            right,
+        })
+    }
+
+    // rule := atom ":-" item ( "," item )*, where an item is either an atom, a negated atom or a comparison
+    pub fn parse_rule(&mut self) -> Result<RuleNode, ParseError> {
+        let (head, head_aggregates) = self.parse_head()?;
+        self.expect(TokenKind::Implication)?;
+        let mut rule = RuleNode {
+            head,
+            head_aggregates,
+            body: Vec::new(),
+            negated_body: Vec::new(),
+            comparisons: Vec::new(),
+        };
+        loop {
+            // An atom is the only item starting with a name followed by a parenthesis, a negated one is preceded by the keyword not
+            let is_atom = matches!(self.peek().kind, TokenKind::Identifier(_))
+                && self.tokens[self.position + 1].kind == TokenKind::LeftParenthesis;
+            let is_negated_atom = self.peek().kind == TokenKind::Identifier(String::from("not"))
+                && matches!(
+                    self.tokens[self.position + 1].kind,
+                    TokenKind::Identifier(_)
+                );
+            if is_negated_atom {
+                self.advance();
+                rule.negated_body.push(self.parse_atom()?);
+            } else if is_atom {
+                rule.body.push(self.parse_atom()?);
+            } else if matches!(
+                self.peek().kind,
+                TokenKind::RightParenthesis | TokenKind::End
+            ) {
+                return Err(self.error("an atom or a comparison"));
+            } else {
+                rule.comparisons.push(self.parse_comparison()?);
+            }
+            if self.peek().kind != TokenKind::Comma {
+                break;
+            }
+            self.advance();
+        }
+        Ok(rule)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Identifier(name) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<usize, ParseError> {
+        if self.is_keyword(keyword) {
+            Ok(self.advance().offset)
+        } else {
+            Err(self.error(format!("'{}'", keyword.to_uppercase()).as_str()))
+        }
+    }
+
+    // column := [ table "." ] name
+    fn parse_column(&mut self) -> Result<ColumnNode, ParseError> {
+        let (name, offset) = self.parse_identifier("a column name")?;
+        if self.peek().kind != TokenKind::Period {
+            return Ok(ColumnNode {
+                table: None,
+                name,
+                offset,
+            });
+        }
+        self.advance();
+        Ok(ColumnNode {
+            table: Some(name),
+            name: self.parse_identifier("a column name")?.0,
+            offset,
+        })
+    }
+
+    // operand := column | number | string
+    fn parse_operand(&mut self) -> Result<OperandNode, ParseError> {
+        match self.peek().kind {
+            TokenKind::Identifier(_) => Ok(OperandNode::Column(self.parse_column()?)),
+            TokenKind::Number(_) | TokenKind::Text(_) => {
+                Ok(OperandNode::Constant(self.parse_term()?))
+            }
+            _ => Err(self.error("a column name or a constant")),
+        }
+    }
+
+    // table := name [ [ "AS" ] alias ]
+    fn parse_table(&mut self) -> Result<TableNode, ParseError> {
+        let (name, offset) = self.parse_identifier("a table name")?;
+        let mut alias = None;
+        if self.is_keyword("as") {
+            self.advance();
+            alias = Some(self.parse_identifier("a table alias")?.0);
+        } else if let TokenKind::Identifier(identifier) = &self.peek().kind {
+            if !SQL_KEYWORDS.contains(&identifier.to_lowercase().as_str()) {
+                alias = Some(identifier.clone());
+                self.advance();
+            }
+        }
+        Ok(TableNode {
+            name,
+            alias,
+            offset,
+        })
+    }
+
+    /*
+        select := "SELECT" [ "DISTINCT" ] ( "*" | column ( "," column )* ) "FROM" table ( "," table )*
+            [ "WHERE" operand operator operand ( "AND" operand operator operand )* ]
+        Keywords are case insensitive.
+    */
+    pub fn parse_select(&mut self) -> Result<SelectNode, ParseError> {
+        self.expect_keyword("select")?;
+        if self.is_keyword("distinct") {
+            self.advance();
+        }
+        let mut select = SelectNode {
+            columns: Vec::new(),
+            offset: self.peek().offset,
+            tables: Vec::new(),
+            conditions: Vec::new(),
+        };
+        if self.peek().kind == TokenKind::Star {
+            self.advance();
+        } else {
+            loop {
+                select.columns.push(self.parse_column()?);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        self.expect_keyword("from")?;
+        loop {
+            select.tables.push(self.parse_table()?);
+            if self.peek().kind != TokenKind::Comma {
+                break;
+            }
+            self.advance();
+        }
+        if self.is_keyword("where") {
+            self.advance();
+            loop {
+                let left = self.parse_operand()?;
+                let operator = match self.peek().kind {
+                    TokenKind::Comparison(operator) => operator,
+                    _ => return Err(self.error("a comparison operator")),
+                };
+                self.advance();
+                let right = self.parse_operand()?;
+                select.conditions.push(ConditionNode {
+                    left,
+                    operator,
+                    right,
+                });
+                if !self.is_keyword("and") {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        Ok(select)
+    }
+
+    pub fn expect_end(&mut self) -> Result<(), ParseError> {
+        if self.peek().kind == TokenKind::End {
+            Ok(())
+        } else {
+            Err(self.error("',', '.' or end of input"))
+        }
+    }
+}
+
+// Parse an input made of exactly one SQL query
+pub fn parse_select(input: &str) -> Result<SelectNode, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let select = parser.parse_select()?;
+    if parser.peek().kind != TokenKind::End {
+        return Err(parser.error("',', 'AND' or end of input"));
+    }
+    Ok(select)
+}
+
+// Parse an input made of exactly one rule, optionally terminated by a period
+pub fn parse_rule(input: &str) -> Result<RuleNode, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let rule = parser.parse_rule()?;
+    if parser.peek().kind == TokenKind::Period {
+        parser.advance();
+    }
+    parser.expect_end()?;
+    Ok(rule)
+}
+
+// Parse an input made of one or more rules separated by periods, the last one can be terminated by a period too
+pub fn parse_rules(input: &str) -> Result<Vec<RuleNode>, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let mut rules = Vec::new();
+    loop {
+        rules.push(parser.parse_rule()?);
+        if parser.peek().kind == TokenKind::Period {
+            parser.advance();
+        } else {
+            parser.expect_end()?;
+        }
+        if parser.peek().kind == TokenKind::End {
+            return Ok(rules);
+        }
+    }
+}
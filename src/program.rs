@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+use crate::{conjunctive_query::ConjunctiveQuery, parse_error::ParseError, parser};
+
+/*
+    Datalog program, i.e. a list of rules whose heads are derived relations that can be used in the bodies of other rules (and of the rule itself), e.g.:
+        path(x, y) :- edge(x, y).
+        path(x, z) :- path(x, y), edge(y, z).
+    Rules deriving the same relation must agree on its arity, and derived relations can't be negated, so that the program has a single least fixpoint.
+*/
+#[derive(Clone, PartialEq)]
+pub struct Program {
+    rules: Vec<ConjunctiveQuery>,
+}
+
+impl Program {
+    pub fn new(text_description: &str) -> Program {
+        match Program::parse(text_description) {
+            Ok(result) => result,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn parse(text_description: &str) -> Result<Program, ParseError> {
+        let rules = parser::parse_rules(text_description)?;
+        let mut arities: HashMap<String, usize> = HashMap::new();
+        for rule in &rules {
+            if let Some(aggregate) = rule.head_aggregates.first() {
+                return Err(ParseError::unexpected(
+                    text_description,
+                    aggregate.offset,
+                    "a variable",
+                    "an aggregate in the head of a program rule",
+                ));
+            }
+            let arity = *arities
+                .entry(rule.head.name.clone())
+                .or_insert(rule.head.terms.len());
+            if arity != rule.head.terms.len() {
+                return Err(ParseError::unexpected(
+                    text_description,
+                    rule.head.offset,
+                    format!("a head {}/{}", rule.head.name, arity).as_str(),
+                    format!("{}/{}", rule.head.name, rule.head.terms.len()).as_str(),
+                ));
+            }
+        }
+        for rule in &rules {
+            for atom_node in &rule.negated_body {
+                if arities.contains_key(&atom_node.name) {
+                    return Err(ParseError::unexpected(
+                        text_description,
+                        atom_node.offset,
+                        "a negated relation not derived by the program",
+                        format!("derived relation '{}'", atom_node.name).as_str(),
+                    ));
+                }
+            }
+        }
+        let mut result = Program { rules: Vec::new() };
+        for rule in &rules {
+            result.rules.push(ConjunctiveQuery::from_valid_rule(
+                text_description,
+                rule,
+                None,
+            )?);
+        }
+        Ok(result)
+    }
+
+    pub fn rules(&self) -> Vec<ConjunctiveQuery> {
+        self.rules.clone()
+    }
+
+    // Names and arities of the relations derived by the program, in order of first appearance
+    pub fn derived_relations(&self) -> Vec<(String, usize)> {
+        let mut result: Vec<(String, usize)> = Vec::new();
+        for rule in &self.rules {
+            if !result.iter().any(|x| x.0 == rule.get_name()) {
+                result.push((rule.get_name(), rule.head().len()));
+            }
+        }
+        result
+    }
+
+    pub fn print(&self) {
+        println!("Number of rules: {}", self.rules.len());
+        for rule in &self.rules {
+            rule.print();
+        }
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}.", rule)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+
+// Names and attributes of the relations a query can refer to, e.g. the tables of a database
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    relations: Vec<(String, Vec<String>)>,
+}
+
+impl Schema {
+    pub fn new() -> Schema {
+        Schema {
+            relations: Vec::new(),
+        }
+    }
+
+    // Add a relation, replacing the one with the same name if any
+    pub fn add_relation(&mut self, name: &str, attributes: Vec<String>) {
+        match self.relations.iter_mut().find(|x| x.0 == name) {
+            Some(relation) => relation.1 = attributes,
+            None => self.relations.push((String::from(name), attributes)),
+        }
+    }
+
+    pub fn relations(&self) -> Vec<String> {
+        self.relations.iter().map(|x| x.0.clone()).collect()
+    }
+
+    pub fn attributes(&self, name: &str) -> Option<Vec<String>> {
+        self.relations
+            .iter()
+            .find(|x| x.0 == name)
+            .map(|x| x.1.clone())
+    }
+
+    pub fn arity(&self, name: &str) -> Option<usize> {
+        self.attributes(name).map(|x| x.len())
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Schema::new()
+    }
+}
+
+impl Display for Schema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, attributes)) in self.relations.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}({})", name, attributes.join(", "))?;
+        }
+        Ok(())
+    }
+}
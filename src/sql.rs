@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use crate::{
+    conjunctive_query::{
+        AggregateFunction, Atom, ComparisonOperator, ConjunctiveQuery, Constant, Term,
+    },
+    diagnostic::{Diagnostic, DiagnosticKind},
+    parse_error::ParseError,
+    parser::{self, AtomNode, ColumnNode, ComparisonNode, OperandNode, RuleNode, TermNode},
+    schema::Schema,
+};
+
+// Name of the conjunctive queries translated from SQL
+const QUERY_NAME: &str = "q";
+
+// How the columns of the relations are named in the exported SQL
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnNaming {
+    // Columns named after their position with the given prefix, e.g. c0, c1, c2 with the prefix c as in positional relations
+    Positional(String),
+    // Columns named as in the schema
+    Schema(Schema),
+}
+
+impl ColumnNaming {
+    fn column(&self, relation: &str, index: usize) -> String {
+        match self {
+            ColumnNaming::Positional(prefix) => format!("{}{}", prefix, index),
+            // The atoms are checked against the schema by to_sql before naming their columns
+            ColumnNaming::Schema(schema) => schema.attributes(relation).unwrap()[index].clone(),
+        }
+    }
+}
+
+// Table of the FROM clause, whose columns are numbered after the ones of the previous tables
+struct FromTable {
+    name: String,
+    alias: String,
+    attributes: Vec<String>,
+    first_column: usize,
+    offset: usize,
+}
+
+/*
+    Translate a select-project-join SQL query into a conjunctive query over the relations of the schema, e.g.
+        SELECT R.a, T.c FROM R, S, T WHERE R.b = S.b AND S.c = T.c
+    becomes q(a, c) :- R(a, b), S(b, c), T(c, d) when the schema is R(a, b), S(b, c), T(c, d).
+    Every table of the FROM clause becomes an atom (a table used more than once needs an alias), and the columns made equal
+    by the WHERE clause share the same variable, named after the column when no other variable has the same name.
+    The other conditions become comparisons. The result has set semantics, as if the query were SELECT DISTINCT.
+*/
+pub fn parse_sql(text_description: &str, schema: &Schema) -> Result<ConjunctiveQuery, ParseError> {
+    let select = parser::parse_select(text_description)?;
+    let mut tables: Vec<FromTable> = Vec::new();
+    let mut columns_number = 0;
+    for table in &select.tables {
+        let attributes = schema.attributes(&table.name).ok_or_else(|| {
+            ParseError::unexpected(
+                text_description,
+                table.offset,
+                "a relation of the schema",
+                format!("unknown relation '{}'", table.name).as_str(),
+            )
+        })?;
+        let alias = table.alias.clone().unwrap_or(table.name.clone());
+        if tables.iter().any(|x| x.alias == alias) {
+            return Err(ParseError::unexpected(
+                text_description,
+                table.offset,
+                "a table name or alias not used before in the FROM clause",
+                format!("duplicate table '{}'", alias).as_str(),
+            ));
+        }
+        tables.push(FromTable {
+            name: table.name.clone(),
+            alias,
+            first_column: columns_number,
+            offset: table.offset,
+            attributes,
+        });
+        columns_number += tables.last().unwrap().attributes.len();
+    }
+
+    // Columns made equal by the WHERE clause are merged with a union-find, every resulting class is a variable
+    let mut parents: Vec<usize> = (0..columns_number).collect();
+    let mut comparisons = Vec::new();
+    for condition in &select.conditions {
+        match (&condition.left, &condition.right) {
+            (OperandNode::Column(left), OperandNode::Column(right))
+                if condition.operator == ComparisonOperator::Equal =>
+            {
+                let left = find(&mut parents, resolve(text_description, &tables, left)?);
+                let right = find(&mut parents, resolve(text_description, &tables, right)?);
+                parents[left.max(right)] = left.min(right);
+            }
+            _ => comparisons.push(condition),
+        }
+    }
+    let mut roots = Vec::new();
+    for column in 0..columns_number {
+        roots.push(find(&mut parents, column));
+    }
+    let variables = name_variables(&tables, &roots);
+
+    let term = |operand: &OperandNode| -> Result<TermNode, ParseError> {
+        match operand {
+            OperandNode::Column(column) => Ok(TermNode {
+                term: Term::Variable(
+                    variables[resolve(text_description, &tables, column)?].clone(),
+                ),
+                offset: column.offset,
+            }),
+            OperandNode::Constant(constant) => Ok(constant.clone()),
+        }
+    };
+    let mut rule = RuleNode {
+        head: AtomNode {
+            name: String::from(QUERY_NAME),
+            terms: Vec::new(),
+            offset: select.offset,
+        },
+        head_aggregates: Vec::new(),
+        body: Vec::new(),
+        negated_body: Vec::new(),
+        comparisons: Vec::new(),
+    };
+    if select.columns.is_empty() {
+        // SELECT * returns all the columns of all the tables
+        for variable in &variables {
+            rule.head.terms.push(TermNode {
+                term: Term::Variable(variable.clone()),
+                offset: select.offset,
+            });
+        }
+    }
+    for column in &select.columns {
+        rule.head
+            .terms
+            .push(term(&OperandNode::Column(column.clone()))?);
+    }
+    for table in &tables {
+        let mut atom = AtomNode {
+            name: table.name.clone(),
+            terms: Vec::new(),
+            offset: table.offset,
+        };
+        for i in 0..table.attributes.len() {
+            atom.terms.push(TermNode {
+                term: Term::Variable(variables[table.first_column + i].clone()),
+                offset: table.offset,
+            });
+        }
+        rule.body.push(atom);
+    }
+    for condition in comparisons {
+        rule.comparisons.push(ComparisonNode {
+            left: term(&condition.left)?,
+            operator: condition.operator,
+            right: term(&condition.right)?,
+        });
+    }
+    ConjunctiveQuery::from_valid_rule(text_description, &rule, Some(schema))
+}
+
+fn find(parents: &mut Vec<usize>, column: usize) -> usize {
+    if parents[column] != column {
+        parents[column] = find(parents, parents[column]);
+    }
+    parents[column]
+}
+
+// Index of the column among the ones of all the tables, an unqualified column must belong to exactly one table
+fn resolve(
+    text_description: &str,
+    tables: &[FromTable],
+    column: &ColumnNode,
+) -> Result<usize, ParseError> {
+    let candidates: Vec<&FromTable> = match &column.table {
+        Some(alias) => {
+            let table = tables.iter().find(|x| x.alias == *alias).ok_or_else(|| {
+                ParseError::unexpected(
+                    text_description,
+                    column.offset,
+                    "a table of the FROM clause",
+                    format!("unknown table '{}'", alias).as_str(),
+                )
+            })?;
+            vec![table]
+        }
+        None => tables
+            .iter()
+            .filter(|x| x.attributes.contains(&column.name))
+            .collect(),
+    };
+    let matches: Vec<usize> = candidates
+        .iter()
+        .filter_map(|x| {
+            x.attributes
+                .iter()
+                .position(|a| *a == column.name)
+                .map(|i| x.first_column + i)
+        })
+        .collect();
+    match matches.len() {
+        1 => Ok(matches[0]),
+        0 => Err(ParseError::unexpected(
+            text_description,
+            column.offset,
+            match &column.table {
+                Some(alias) => format!("a column of {}", alias),
+                None => String::from("a column of a table of the FROM clause"),
+            }
+            .as_str(),
+            format!("unknown column '{}'", column.name).as_str(),
+        )),
+        _ => Err(ParseError::unexpected(
+            text_description,
+            column.offset,
+            "a column qualified by its table",
+            format!("ambiguous column '{}'", column.name).as_str(),
+        )),
+    }
+}
+
+/*
+    Name the variable of every column after the first column of its class, qualifying it with the table alias
+    when columns with the same name belong to different classes, and adding a number if the name is still taken.
+*/
+fn name_variables(tables: &[FromTable], roots: &[usize]) -> Vec<String> {
+    let mut columns = Vec::new();
+    for table in tables {
+        for attribute in &table.attributes {
+            columns.push((table.alias.clone(), attribute.clone()));
+        }
+    }
+    let mut names: Vec<Option<String>> = vec![None; roots.len()];
+    let mut used: Vec<String> = Vec::new();
+    for column in 0..roots.len() {
+        let root = roots[column];
+        if root != column {
+            continue;
+        }
+        let (alias, attribute) = &columns[column];
+        let shared = (0..roots.len())
+            .any(|x| roots[x] != root && roots[x] == x && columns[x].1 == *attribute);
+        let mut name = if shared {
+            format!("{}_{}", alias, attribute)
+        } else {
+            attribute.clone()
+        };
+        let mut k = 1;
+        while used.contains(&name) {
+            k += 1;
+            name = format!("{}{}", attribute, k);
+        }
+        used.push(name.clone());
+        names[column] = Some(name);
+    }
+    (0..roots.len())
+        .map(|x| names[roots[x]].clone().unwrap())
+        .collect()
+}
+
+/*
+    Render a conjunctive query as an equivalent SQL statement, e.g. q(x, z) :- R(x, y), S(y, z), z > 3 with positional naming becomes
+        SELECT DISTINCT t1.c0 AS x, t2.c1 AS z FROM R AS t1, S AS t2 WHERE t2.c0 = t1.c1 AND t2.c1 > 3
+    Every atom is a table of the FROM clause, every variable is the column of its first occurrence and the other occurrences are
+    equalities. Negated atoms become NOT EXISTS subqueries, boolean queries SELECT EXISTS and aggregate queries GROUP BY the head
+    variables, which gives the same counts and sums as long as the relations have no duplicate records.
+    With schema naming, the first atom whose relation is not in the schema or has a different arity is returned as an error.
+*/
+pub fn to_sql(cq: &ConjunctiveQuery, naming: &ColumnNaming) -> Result<String, Diagnostic> {
+    if let ColumnNaming::Schema(schema) = naming {
+        let mismatch = cq.validate(Some(schema)).into_iter().find(|x| {
+            matches!(
+                x.kind(),
+                DiagnosticKind::UnknownRelation(_) | DiagnosticKind::ArityMismatch { .. }
+            )
+        });
+        if let Some(diagnostic) = mismatch {
+            return Err(diagnostic);
+        }
+    }
+    let mut columns = HashMap::new();
+    let mut tables = Vec::new();
+    let mut conditions = Vec::new();
+    for (i, atom) in cq.atoms().iter().enumerate() {
+        let alias = format!("t{}", i + 1);
+        tables.push(format!("{} AS {}", atom.get_relation_name(), alias));
+        conditions.extend(term_conditions(atom, &alias, naming, &mut columns));
+    }
+    for (i, atom) in cq.negated_atoms().iter().enumerate() {
+        let alias = format!("n{}", i + 1);
+        let negated_conditions = term_conditions(atom, &alias, naming, &mut columns.clone());
+        let where_clause = if negated_conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", negated_conditions.join(" AND "))
+        };
+        conditions.push(format!(
+            "NOT EXISTS (SELECT * FROM {} AS {}{})",
+            atom.get_relation_name(),
+            alias,
+            where_clause
+        ));
+    }
+    let operand = |term: &Term| match term {
+        Term::Variable(variable) => columns[variable].clone(),
+        Term::Constant(constant) => sql_constant(constant),
+    };
+    for comparison in &cq.comparisons() {
+        let operator = match comparison.get_operator() {
+            ComparisonOperator::NotEqual => String::from("<>"),
+            operator => operator.to_string(),
+        };
+        conditions.push(format!(
+            "{} {} {}",
+            operand(&comparison.get_left()),
+            operator,
+            operand(&comparison.get_right())
+        ));
+    }
+    let from_where = if conditions.is_empty() {
+        format!("FROM {}", tables.join(", "))
+    } else {
+        format!(
+            "FROM {} WHERE {}",
+            tables.join(", "),
+            conditions.join(" AND ")
+        )
+    };
+    if cq.is_boolean() {
+        return Ok(format!("SELECT EXISTS (SELECT * {})", from_where));
+    }
+
+    let head_columns: Vec<String> = cq.head().iter().map(|x| columns[x].clone()).collect();
+    let mut select_list: Vec<String> = head_columns
+        .iter()
+        .zip(cq.head())
+        .map(|(column, variable)| format!("{} AS {}", column, variable))
+        .collect();
+    for (position, aggregate) in &cq.aggregates() {
+        let function = match aggregate.get_function() {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        };
+        let argument = match aggregate.get_variable() {
+            Some(variable) => columns[&variable].clone(),
+            None => String::from("*"),
+        };
+        select_list.insert(
+            *position,
+            format!("{}({}) AS \"{}\"", function, argument, aggregate),
+        );
+    }
+    if !cq.is_aggregate() {
+        return Ok(format!(
+            "SELECT DISTINCT {} {}",
+            select_list.join(", "),
+            from_where
+        ));
+    }
+    if head_columns.is_empty() {
+        return Ok(format!("SELECT {} {}", select_list.join(", "), from_where));
+    }
+    Ok(format!(
+        "SELECT {} {} GROUP BY {}",
+        select_list.join(", "),
+        from_where,
+        head_columns.join(", ")
+    ))
+}
+
+// Conditions binding the terms of an atom to the columns of its table, the first column of a variable becomes the column of that variable
+fn term_conditions(
+    atom: &Atom,
+    alias: &str,
+    naming: &ColumnNaming,
+    columns: &mut HashMap<String, String>,
+) -> Vec<String> {
+    let mut result = Vec::new();
+    for (i, term) in atom.get_terms().iter().enumerate() {
+        let column = format!("{}.{}", alias, naming.column(&atom.get_relation_name(), i));
+        match term {
+            Term::Variable(variable) => match columns.get(variable) {
+                Some(first_column) => result.push(format!("{} = {}", column, first_column)),
+                None => {
+                    columns.insert(variable.clone(), column);
+                }
+            },
+            Term::Constant(constant) => {
+                result.push(format!("{} = {}", column, sql_constant(constant)))
+            }
+        }
+    }
+    result
+}
+
+// Strings are delimited by single quotes in SQL, where a quote is escaped by doubling it
+fn sql_constant(constant: &Constant) -> String {
+    match constant {
+        Constant::Number(value) => value.clone(),
+        Constant::Text(value) => format!("'{}'", value.replace('\'', "''")),
+    }
+}
@@ -0,0 +1,209 @@
+/*
+    Relation stored as its records sorted lexicographically, read as a trie whose levels are its columns:
+    the children of a node at depth d are the distinct values at column d of the range of records sharing the values of the previous columns.
+    A node is a range of records, and a child is found by binary search, so that no pointer structure has to be built.
+*/
+#[derive(Clone, Debug)]
+pub struct Trie<T: Ord + Copy> {
+    variables: Vec<String>,
+    records: Vec<Vec<T>>,
+}
+
+impl<T: Ord + Copy> Trie<T> {
+    // Build the trie of a table whose columns are named by the given attributes, taking the columns in the given variable order
+    pub fn new(attributes: &[String], records: &[Vec<T>], order: &[String]) -> Trie<T> {
+        let mut columns: Vec<usize> = (0..attributes.len()).collect();
+        columns.sort_by_key(|i| {
+            order
+                .iter()
+                .position(|x| *x == attributes[*i])
+                .unwrap_or_else(|| panic!("Attribute {} not in the variable order", attributes[*i]))
+        });
+        let mut records: Vec<Vec<T>> = records
+            .iter()
+            .map(|record| columns.iter().map(|i| record[*i]).collect())
+            .collect();
+        records.sort();
+        records.dedup();
+        Trie {
+            variables: columns.iter().map(|i| attributes[*i].clone()).collect(),
+            records,
+        }
+    }
+
+    pub fn variables(&self) -> Vec<String> {
+        self.variables.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    // First record of the range from start to end whose value at the depth is not less than the given one
+    fn seek(&self, start: usize, end: usize, depth: usize, value: T) -> usize {
+        start + self.records[start..end].partition_point(|x| x[depth] < value)
+    }
+
+    // First record of the range from start to end whose value at the depth is greater than the given one
+    fn skip(&self, start: usize, end: usize, depth: usize, value: T) -> usize {
+        start + self.records[start..end].partition_point(|x| x[depth] <= value)
+    }
+}
+
+/*
+    Leapfrog Triejoin: natural join of all the tries at once, binding the variables one at a time in the given order,
+    which must contain the variables of every trie. The values of a variable are the intersection of the children of the current nodes
+    of the tries containing it: every round takes the largest of their current values and seeks every trie to it by binary search,
+    until they all agree on it, that is then a value of the intersection.
+    A round that finds no value raises the largest value past the current one of some trie, so that every child of the trie with the fewest
+    children is current for at most a few rounds: an intersection costs its smallest input up to a logarithmic factor,
+    which is enough for the whole join to run within the AGM bound on the size of its result, whatever the order (Ngo, Porat, Re, Rudra).
+    Returns the records of the join over the variables in the order.
+*/
+pub fn leapfrog_triejoin<T: Ord + Copy>(tries: &[Trie<T>], order: &[String]) -> Vec<Vec<T>> {
+    let mut result = Vec::new();
+    // An empty relation empties the join, and it is the only way a trie without variables is taken into account
+    if tries.iter().any(|x| x.is_empty()) {
+        return result;
+    }
+    let mut ranges: Vec<(usize, usize)> = tries.iter().map(|x| (0, x.len())).collect();
+    let mut tuple = Vec::new();
+    extend(tries, order, &mut ranges, &mut tuple, &mut result);
+    result
+}
+
+// Bind the next variable of the order to every value in the intersection, and recurse on the ranges of the tries under that value
+fn extend<T: Ord + Copy>(
+    tries: &[Trie<T>],
+    order: &[String],
+    ranges: &mut [(usize, usize)],
+    tuple: &mut Vec<T>,
+    result: &mut Vec<Vec<T>>,
+) {
+    let Some(variable) = order.get(tuple.len()) else {
+        result.push(tuple.clone());
+        return;
+    };
+    // Tries containing the variable, with the depth of its column, that is the number of their variables already bound
+    let participants: Vec<(usize, usize)> = (tries.iter().enumerate())
+        .filter_map(|(i, x)| {
+            x.variables
+                .iter()
+                .position(|y| y == variable)
+                .map(|d| (i, d))
+        })
+        .collect();
+    if participants.is_empty() {
+        panic!("Variable {} not in any trie", variable);
+    }
+    let mut positions: Vec<usize> = participants.iter().map(|(i, _)| ranges[*i].0).collect();
+    let ends: Vec<usize> = participants.iter().map(|(i, _)| ranges[*i].1).collect();
+    let value_at = |k: usize, position: usize| {
+        let (i, depth) = participants[k];
+        tries[i].records[position][depth]
+    };
+    loop {
+        let mut largest = value_at(0, positions[0]);
+        for (k, position) in positions.iter().enumerate() {
+            largest = largest.max(value_at(k, *position));
+        }
+        let mut agree = true;
+        for (k, (i, depth)) in participants.iter().enumerate() {
+            positions[k] = tries[*i].seek(positions[k], ends[k], *depth, largest);
+            if positions[k] == ends[k] {
+                return;
+            }
+            agree &= value_at(k, positions[k]) == largest;
+        }
+        if !agree {
+            continue;
+        }
+        let saved: Vec<(usize, usize)> = participants.iter().map(|(i, _)| ranges[*i]).collect();
+        let mut nexts = Vec::new();
+        for (k, (i, depth)) in participants.iter().enumerate() {
+            let next = tries[*i].skip(positions[k], ends[k], *depth, largest);
+            ranges[*i] = (positions[k], next);
+            nexts.push(next);
+        }
+        tuple.push(largest);
+        extend(tries, order, ranges, tuple, result);
+        tuple.pop();
+        for (k, (i, _)) in participants.iter().enumerate() {
+            ranges[*i] = saved[k];
+        }
+        positions = nexts;
+        if positions
+            .iter()
+            .zip(&ends)
+            .any(|(position, end)| position == end)
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| String::from(*x)).collect()
+    }
+
+    #[test]
+    fn seek_and_skip() {
+        let trie = Trie::new(
+            &variables(&["x", "y"]),
+            &[vec![3, 1], vec![1, 2], vec![3, 0], vec![5, 4], vec![1, 2]],
+            &variables(&["x", "y"]),
+        );
+        // Records are sorted and deduplicated: (1, 2), (3, 0), (3, 1), (5, 4)
+        assert_eq!(trie.len(), 4);
+        assert_eq!(trie.seek(0, 4, 0, 3), 1);
+        assert_eq!(trie.seek(0, 4, 0, 2), 1);
+        assert_eq!(trie.seek(0, 4, 0, 6), 4);
+        assert_eq!(trie.skip(0, 4, 0, 3), 3);
+        // Inside the range of x = 3, the column of y is sorted too
+        assert_eq!(trie.seek(1, 3, 1, 1), 2);
+        assert_eq!(trie.seek(1, 3, 1, 2), 3);
+    }
+
+    #[test]
+    fn columns_follow_the_variable_order() {
+        let trie = Trie::new(
+            &variables(&["x", "y"]),
+            &[vec![1, 2], vec![2, 1]],
+            &variables(&["y", "x"]),
+        );
+        assert_eq!(trie.variables(), variables(&["y", "x"]));
+        assert_eq!(trie.records, vec![vec![1, 2], vec![2, 1]]);
+    }
+
+    #[test]
+    fn triangle() {
+        let order = variables(&["x", "y", "z"]);
+        let edges = [vec![1, 2], vec![2, 3], vec![3, 1], vec![1, 3], vec![2, 4]];
+        let tries = [
+            Trie::new(&variables(&["x", "y"]), &edges, &order),
+            Trie::new(&variables(&["y", "z"]), &edges, &order),
+            Trie::new(&variables(&["z", "x"]), &edges, &order),
+        ];
+        let result = leapfrog_triejoin(&tries, &order);
+        assert_eq!(result, vec![vec![1, 2, 3], vec![2, 3, 1], vec![3, 1, 2]]);
+    }
+
+    #[test]
+    fn empty_intersection() {
+        let order = variables(&["x", "y"]);
+        let tries = [
+            Trie::new(&variables(&["x"]), &[vec![1], vec![3], vec![5]], &order),
+            Trie::new(&variables(&["x"]), &[vec![2], vec![4], vec![6]], &order),
+            Trie::new(&variables(&["x", "y"]), &[vec![1, 1], vec![2, 2]], &order),
+        ];
+        assert!(leapfrog_triejoin(&tries, &order).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    conjunctive_query::ConjunctiveQuery, hyper_graph::HyperGraph, parse_error::ParseError, parser,
+};
+
+// Union of conjunctive queries (disjuncts) sharing the same head name and arity, e.g. q(x) :- R(x, y). q(x) :- S(x)
+#[derive(Clone, PartialEq)]
+pub struct UnionQuery {
+    disjuncts: Vec<ConjunctiveQuery>,
+}
+
+impl UnionQuery {
+    pub fn new(text_description: &str) -> UnionQuery {
+        match UnionQuery::parse(text_description) {
+            Ok(result) => result,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn parse(text_description: &str) -> Result<UnionQuery, ParseError> {
+        let rules = parser::parse_rules(text_description)?;
+        let first_head = &rules[0].head;
+        // Aggregates count as head terms
+        let arity = first_head.terms.len() + rules[0].head_aggregates.len();
+        for rule in &rules[1..] {
+            let rule_arity = rule.head.terms.len() + rule.head_aggregates.len();
+            if rule.head.name != first_head.name || rule_arity != arity {
+                return Err(ParseError::unexpected(
+                    text_description,
+                    rule.head.offset,
+                    format!("a head {}/{}", first_head.name, arity).as_str(),
+                    format!("{}/{}", rule.head.name, rule_arity).as_str(),
+                ));
+            }
+        }
+        let mut result = UnionQuery {
+            disjuncts: Vec::new(),
+        };
+        for rule in &rules {
+            let disjunct = ConjunctiveQuery::from_valid_rule(text_description, rule, None)?;
+            // The union is evaluated with Yannakakis algorithm, that needs every disjunct to be alpha-acyclic
+            if !HyperGraph::new(&disjunct).is_acyclic() {
+                return Err(ParseError::unexpected(
+                    text_description,
+                    rule.head.offset,
+                    "an alpha-acyclic disjunct",
+                    "a cyclic one",
+                ));
+            }
+            result.disjuncts.push(disjunct);
+        }
+        Ok(result)
+    }
+
+    pub fn disjuncts(&self) -> Vec<ConjunctiveQuery> {
+        self.disjuncts.clone()
+    }
+
+    pub fn get_name(&self) -> String {
+        self.disjuncts[0].get_name()
+    }
+
+    pub fn arity(&self) -> usize {
+        self.disjuncts[0].head_attributes().len()
+    }
+
+    pub fn print(&self) {
+        println!("Union query name: {}", self.get_name());
+        println!("Number of disjuncts: {}", self.disjuncts.len());
+        for disjunct in &self.disjuncts {
+            disjunct.print();
+        }
+    }
+}
+
+impl Display for UnionQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, disjunct) in self.disjuncts.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}.", disjunct)?;
+        }
+        Ok(())
+    }
+}